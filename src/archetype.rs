@@ -0,0 +1,356 @@
+//! Grouping decks of one class into archetypes by the cards they share.
+//!
+//! Decks are compared by [`Similarity`] and split into clusters by k-medoids: each cluster is
+//! centred on one of its own decks, the one closest to the rest. A cluster is labelled by the
+//! minion type or spell school most of its cards share, as in "Pirate Rogue", or failing that by
+//! its most distinctive card: the one most often played in the cluster compared with the rest of
+//! the decks.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::deck::Deck;
+use crate::{CardInfo, MinionType, SpellSchool};
+
+/// Rounds of reassigning decks before k-medoids gives up on settling.
+const MAX_ROUNDS: usize = 100;
+/// Distinctive cards kept for each archetype.
+const DISTINCTIVE_CARDS: usize = 3;
+/// The share of a cluster's cards a minion type or spell school needs to name it.
+const DOMINANT_SHARE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Similarity {
+    /// Shared cards over all cards in either deck, ignoring copy counts.
+    Jaccard,
+    /// The cosine of the angle between the decks' copy counts.
+    Cosine,
+}
+
+impl Similarity {
+    /// How alike two decks are, from 0 (no cards in common) to 1 (the same cards).
+    pub fn between(self, a: &Deck, b: &Deck) -> f64 {
+        match self {
+            Similarity::Jaccard => {
+                let a: HashSet<u32> = a.cards.iter().map(|(card, _)| card.id()).collect();
+                let b: HashSet<u32> = b.cards.iter().map(|(card, _)| card.id()).collect();
+                let union = a.union(&b).count();
+                if union == 0 {
+                    return 1.0;
+                }
+                a.intersection(&b).count() as f64 / union as f64
+            }
+            Similarity::Cosine => {
+                let norm = |deck: &Deck| {
+                    deck.cards
+                        .iter()
+                        .map(|&(_, count)| (count as f64).powi(2))
+                        .sum::<f64>()
+                        .sqrt()
+                };
+                let dot: f64 = a
+                    .cards
+                    .iter()
+                    .map(|(card, count)| *count as f64 * b.count(card.id()) as f64)
+                    .sum();
+                match norm(a) * norm(b) {
+                    0.0 => 1.0,
+                    norms => dot / norms,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Archetype {
+    pub label: String,
+    /// Indices of the decks in the cluster, in input order.
+    pub decks: Vec<usize>,
+    /// The index of the deck most like the others in the cluster.
+    pub medoid: usize,
+    /// Ids of the cards most characteristic of the cluster, most distinctive first.
+    pub distinctive: Vec<u32>,
+    pub minion_type: Option<MinionType>,
+    pub spell_school: Option<SpellSchool>,
+}
+
+impl fmt::Display for Archetype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decks = if self.decks.len() == 1 {
+            "deck"
+        } else {
+            "decks"
+        };
+        write!(f, "{} ({} {decks})", self.label, self.decks.len())
+    }
+}
+
+/// Splits `decks` into up to `k` archetypes, largest first. The decks are expected to share a
+/// class; labels use the class of each cluster's medoid. Clusters left without decks, as happens
+/// when there are fewer distinct decks than `k`, are dropped.
+pub fn cluster(decks: &[Deck], k: usize, similarity: Similarity) -> Vec<Archetype> {
+    let k = k.min(decks.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    let distance: Vec<Vec<f64>> = decks
+        .iter()
+        .map(|a| {
+            decks
+                .iter()
+                .map(|b| 1.0 - similarity.between(a, b))
+                .collect()
+        })
+        .collect();
+    let medoids = k_medoids(&distance, k);
+    let assignment = assign(&distance, &medoids);
+
+    let mut archetypes: Vec<Archetype> = medoids
+        .iter()
+        .enumerate()
+        .filter_map(|(cluster, &medoid)| {
+            let members: Vec<usize> = (0..decks.len())
+                .filter(|&deck| assignment[deck] == cluster)
+                .collect();
+            (!members.is_empty()).then(|| describe(decks, members, medoid))
+        })
+        .collect();
+    archetypes
+        .sort_by_key(|archetype| (std::cmp::Reverse(archetype.decks.len()), archetype.medoid));
+
+    let mut seen = HashSet::new();
+    for archetype in &mut archetypes {
+        if !seen.insert(archetype.label.clone())
+            && let Some(&id) = archetype.distinctive.first()
+            && let Some((card, _)) = decks[archetype.medoid]
+                .cards
+                .iter()
+                .find(|(c, _)| c.id() == id)
+        {
+            archetype.label = format!("{} ({})", archetype.label, card.name());
+        }
+    }
+    archetypes
+}
+
+/// Picks `k` medoids: greedily at first, each one cutting the total distance the most, then
+/// moving each to the member of its cluster closest to the others until nothing changes.
+fn k_medoids(distance: &[Vec<f64>], k: usize) -> Vec<usize> {
+    let n = distance.len();
+    let cost = |medoids: &[usize]| -> f64 {
+        (0..n)
+            .map(|i| {
+                medoids
+                    .iter()
+                    .map(|&m| distance[i][m])
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum()
+    };
+    let mut medoids: Vec<usize> = Vec::with_capacity(k);
+    while medoids.len() < k {
+        let best = (0..n)
+            .filter(|i| !medoids.contains(i))
+            .min_by(|&a, &b| {
+                let with = |i| cost(&[medoids.as_slice(), &[i]].concat());
+                with(a).total_cmp(&with(b))
+            })
+            .expect("k is at most the number of decks");
+        medoids.push(best);
+    }
+
+    for _ in 0..MAX_ROUNDS {
+        let assignment = assign(distance, &medoids);
+        let moved: Vec<usize> = (0..k)
+            .map(|cluster| {
+                let members: Vec<usize> = (0..n).filter(|&i| assignment[i] == cluster).collect();
+                let spread = |m: usize| members.iter().map(|&i| distance[i][m]).sum::<f64>();
+                members
+                    .iter()
+                    .copied()
+                    .min_by(|&a, &b| spread(a).total_cmp(&spread(b)))
+                    .unwrap_or(medoids[cluster])
+            })
+            .collect();
+        if moved == medoids {
+            break;
+        }
+        medoids = moved;
+    }
+    medoids
+}
+
+/// The index in `medoids` of the nearest medoid to each deck, the earliest on ties.
+fn assign(distance: &[Vec<f64>], medoids: &[usize]) -> Vec<usize> {
+    distance
+        .iter()
+        .map(|row| {
+            (0..medoids.len())
+                .min_by(|&a, &b| row[medoids[a]].total_cmp(&row[medoids[b]]))
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+fn describe(decks: &[Deck], members: Vec<usize>, medoid: usize) -> Archetype {
+    // The share of decks in and out of the cluster playing each card.
+    let share = |decks: &mut dyn Iterator<Item = &Deck>| {
+        let mut total = 0;
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for deck in decks {
+            total += 1;
+            for (card, _) in &deck.cards {
+                *counts.entry(card.id()).or_default() += 1;
+            }
+        }
+        (counts, total.max(1) as f64)
+    };
+    let (inside, in_total) = share(&mut members.iter().map(|&i| &decks[i]));
+    let (outside, out_total) = share(
+        &mut (0..decks.len())
+            .filter(|i| !members.contains(i))
+            .map(|i| &decks[i]),
+    );
+    let mut distinctive: Vec<(u32, f64)> = inside
+        .iter()
+        .map(|(&id, &count)| {
+            let elsewhere = outside.get(&id).copied().unwrap_or(0) as f64 / out_total;
+            (id, count as f64 / in_total - elsewhere)
+        })
+        .filter(|&(_, score)| score > 0.0)
+        .collect();
+    distinctive.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    distinctive.truncate(DISTINCTIVE_CARDS);
+
+    let mut tribes: HashMap<MinionType, usize> = HashMap::new();
+    let mut schools: HashMap<SpellSchool, usize> = HashMap::new();
+    let mut cards = 0;
+    for &i in &members {
+        for (card, count) in &decks[i].cards {
+            let count = *count as usize;
+            cards += count;
+            for &tribe in card.minion_types() {
+                *tribes.entry(tribe).or_default() += count;
+            }
+            if let Some(school) = card.spell_school() {
+                *schools.entry(school).or_default() += count;
+            }
+        }
+    }
+    fn dominant<T: Copy + fmt::Display>(
+        counts: &HashMap<T, usize>,
+        cards: usize,
+    ) -> Option<(T, usize)> {
+        counts
+            .iter()
+            .map(|(&value, &count)| (value, count))
+            .filter(|&(_, count)| count as f64 >= DOMINANT_SHARE * cards as f64)
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.to_string().cmp(&a.0.to_string())))
+    }
+    let minion_type = dominant(&tribes, cards);
+    let spell_school = dominant(&schools, cards);
+
+    let class = decks[medoid].class;
+    let label = match (minion_type, spell_school) {
+        (Some((tribe, a)), Some((_, b))) if a >= b => format!("{tribe} {class}"),
+        (Some((tribe, _)), None) => format!("{tribe} {class}"),
+        (_, Some((school, _))) => format!("{school} {class}"),
+        (None, None) => {
+            let name = distinctive.first().and_then(|&(id, _)| {
+                members.iter().find_map(|&i| {
+                    decks[i]
+                        .cards
+                        .iter()
+                        .find(|(card, _)| card.id() == id)
+                        .map(|(card, _)| card.name().to_string())
+                })
+            });
+            match name {
+                Some(name) => format!("{name} {class}"),
+                None => class.to_string(),
+            }
+        }
+    };
+    Archetype {
+        label,
+        decks: members,
+        medoid,
+        distinctive: distinctive.into_iter().map(|(id, _)| id).collect(),
+        minion_type: minion_type.map(|(tribe, _)| tribe),
+        spell_school: spell_school.map(|(school, _)| school),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Format;
+    use crate::game::test_cards;
+    use crate::{Card, CardSet, Class, OwnedCard, Rarity};
+    use std::borrow::Cow;
+
+    fn minion(id: u32, tribe: Option<MinionType>) -> OwnedCard {
+        let mut card = test_cards::minion(id, 2, 2, 2, "");
+        if let Card::Minion { minion_types, .. } = &mut card {
+            minion_types.extend(tribe);
+        }
+        card
+    }
+
+    fn spell(id: u32) -> OwnedCard {
+        Card::Spell {
+            id,
+            name: Cow::Owned(format!("Spell {id}")),
+            mana_cost: 2,
+            spell_school: SpellSchool::Shadow,
+            classes: vec![Class::Rogue],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(""),
+        }
+    }
+
+    fn deck(cards: impl IntoIterator<Item = OwnedCard>) -> Deck {
+        let mut deck = Deck::new(Format::Standard, Class::Rogue);
+        for card in cards {
+            deck.add(card, 2);
+        }
+        deck
+    }
+
+    #[test]
+    fn groups_decks_by_shared_cards() {
+        let pirates = |extra: u32| {
+            deck(
+                (1..=8)
+                    .map(|id| minion(id, Some(MinionType::Pirate)))
+                    .chain([minion(extra, None)]),
+            )
+        };
+        let shadow = |extra: u32| deck((20..=27).map(spell).chain([minion(extra, None)]));
+        let decks = [pirates(40), shadow(41), pirates(42), shadow(43), shadow(44)];
+        assert_eq!(Similarity::Jaccard.between(&decks[0], &decks[0]), 1.0);
+        assert!(Similarity::Cosine.between(&decks[0], &decks[2]) > 0.8);
+        assert_eq!(Similarity::Cosine.between(&decks[0], &decks[1]), 0.0);
+
+        let archetypes = cluster(&decks, 2, Similarity::Jaccard);
+        assert_eq!(archetypes.len(), 2);
+        assert_eq!(archetypes[0].label, "Shadow Rogue");
+        assert_eq!(archetypes[0].decks, [1, 3, 4]);
+        assert_eq!(archetypes[0].spell_school, Some(SpellSchool::Shadow));
+        assert_eq!(archetypes[1].label, "Pirate Rogue");
+        assert_eq!(archetypes[1].decks, [0, 2]);
+        assert_eq!(archetypes[1].distinctive, [1, 2, 3]);
+        assert_eq!(archetypes[1].to_string(), "Pirate Rogue (2 decks)");
+    }
+
+    #[test]
+    fn identical_decks_make_one_archetype() {
+        let d = deck((20..=27).map(spell));
+        let archetypes = cluster(&[d.clone(), d.clone(), d], 2, Similarity::Jaccard);
+        assert_eq!(archetypes.len(), 1);
+        assert_eq!(archetypes[0].decks, [0, 1, 2]);
+        assert!(archetypes[0].decks.contains(&archetypes[0].medoid));
+    }
+}
@@ -0,0 +1,204 @@
+//! Running many seeded games at once on a pool of worker threads.
+//!
+//! Jobs go to the workers over a shared channel and results come back over another as each one
+//! finishes, so they arrive in no particular order but tagged with the index of their job. Every
+//! job carries its own seed and gets fresh players, so what a job produces doesn't depend on which
+//! worker ran it or what ran before, and totals are the same for any number of workers.
+
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::OwnedCard;
+use crate::bot::{MatchReport, Player, play_game};
+use crate::game::Outcome;
+
+/// A number of worker threads to run jobs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pool {
+    workers: usize,
+}
+
+impl Pool {
+    /// A pool of `workers` threads, or one if `workers` is zero.
+    pub fn new(workers: usize) -> Pool {
+        Pool {
+            workers: workers.max(1),
+        }
+    }
+
+    /// A pool with a thread for each core, as far as the system can tell.
+    pub fn available() -> Pool {
+        Pool::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Starts running `work` on each job, returning the results as they come in.
+    pub fn run<J, R, F>(&self, jobs: Vec<J>, work: F) -> Results<R>
+    where
+        J: Send + 'static,
+        R: Send + 'static,
+        F: Fn(J) -> R + Send + Sync + 'static,
+    {
+        let (job_tx, job_rx) = mpsc::channel();
+        for job in jobs.into_iter().enumerate() {
+            job_tx.send(job).expect("the receiver is still held here");
+        }
+        drop(job_tx);
+
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let work = Arc::new(work);
+        let (tx, rx) = mpsc::channel();
+        let workers = (0..self.workers)
+            .map(|_| {
+                let (job_rx, work, tx) = (Arc::clone(&job_rx), Arc::clone(&work), tx.clone());
+                thread::spawn(move || {
+                    loop {
+                        // The lock is released before the job runs, so others can take the next.
+                        let job = job_rx.lock().unwrap().recv();
+                        let Ok((index, job)) = job else {
+                            break;
+                        };
+                        if tx.send((index, work(job))).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        Results { rx, workers }
+    }
+}
+
+/// Results of a [`Pool::run`], each with the index of its job, in the order they finish.
+///
+/// Dropping it early lets the workers stop after their current job. If a job panics, the panic
+/// is passed on once the other results have been read.
+#[derive(Debug)]
+pub struct Results<R> {
+    rx: Receiver<(usize, R)>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<R> Iterator for Results<R> {
+    type Item = (usize, R);
+
+    fn next(&mut self) -> Option<(usize, R)> {
+        if let Ok(result) = self.rx.recv() {
+            return Some(result);
+        }
+        for worker in self.workers.drain(..) {
+            if let Err(panic) = worker.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        None
+    }
+}
+
+/// One game of a matchup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchJob {
+    /// The index of the matchup.
+    pub matchup: usize,
+    pub seed: u64,
+}
+
+/// Plays `games` games of each pair of decks in `matchups`, with `players` choosing who plays
+/// each deck in each game. Game `i` of every matchup is seeded with `seed + i`, as in
+/// [`run_match`](crate::bot::run_match). Results stream back as each game finishes.
+pub fn play_matchups<F>(
+    pool: &Pool,
+    matchups: Vec<[Vec<OwnedCard>; 2]>,
+    games: u32,
+    seed: u64,
+    players: F,
+) -> Results<(MatchJob, Option<Outcome>)>
+where
+    F: Fn(&MatchJob) -> [Box<dyn Player>; 2] + Send + Sync + 'static,
+{
+    let jobs: Vec<MatchJob> = (0..matchups.len())
+        .flat_map(|matchup| {
+            (0..games).map(move |i| MatchJob {
+                matchup,
+                seed: seed.wrapping_add(i as u64),
+            })
+        })
+        .collect();
+    let matchups = Arc::new(matchups);
+    pool.run(jobs, move |job| {
+        let [first, second] = &matchups[job.matchup];
+        let [mut a, mut b] = players(&job);
+        let game = play_game([first, second], [&mut *a, &mut *b], job.seed);
+        (job, game.outcome())
+    })
+}
+
+/// Plays the matchups as [`play_matchups`] does and totals each one.
+pub fn run_matchups<F>(
+    pool: &Pool,
+    matchups: Vec<[Vec<OwnedCard>; 2]>,
+    games: u32,
+    seed: u64,
+    players: F,
+) -> Vec<MatchReport>
+where
+    F: Fn(&MatchJob) -> [Box<dyn Player>; 2] + Send + Sync + 'static,
+{
+    let mut reports = vec![MatchReport::default(); matchups.len()];
+    for (_, (job, outcome)) in play_matchups(pool, matchups, games, seed, players) {
+        if let Some(outcome) = outcome {
+            reports[job.matchup].record(outcome);
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{GreedyBot, RandomBot, run_match};
+    use crate::game::test_cards::deck;
+
+    #[test]
+    fn streams_every_job_once() {
+        let mut results: Vec<(usize, u64)> =
+            Pool::new(3).run((0..50u64).collect(), |n| n * n).collect();
+        results.sort();
+        assert_eq!(
+            results,
+            (0..50).map(|n| (n as usize, n * n)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn totals_do_not_depend_on_worker_count() {
+        let matchups = || vec![[deck(3), deck(1)], [deck(2), deck(2)]];
+        let random = |job: &MatchJob| -> [Box<dyn Player>; 2] {
+            [
+                Box::new(RandomBot::new(job.seed)),
+                Box::new(RandomBot::new(!job.seed)),
+            ]
+        };
+        let one = run_matchups(&Pool::new(1), matchups(), 12, 5, random);
+        let four = run_matchups(&Pool::new(4), matchups(), 12, 5, random);
+        assert_eq!(one, four);
+        assert_eq!(one[1].games, 12);
+
+        let greedy = run_matchups(
+            &Pool::new(4),
+            matchups(),
+            6,
+            5,
+            |_: &MatchJob| -> [Box<dyn Player>; 2] { [Box::new(GreedyBot), Box::new(GreedyBot)] },
+        );
+        let [strong, weak] = &matchups()[0];
+        assert_eq!(
+            greedy[0],
+            run_match([strong, weak], &mut GreedyBot, &mut GreedyBot, 6, 5)
+        );
+    }
+}
@@ -0,0 +1,436 @@
+//! Automated opponents, and a harness that pits two of them against each other.
+//!
+//! A [`Player`] is asked for one action at a time and only sees a [`View`]: its own hand, both
+//! boards and heroes, and how many cards are in each deck and in the opponent's hand. The view
+//! can be played forward to try actions out, with hidden cards standing in for the unknown ones.
+//! An action the game rejects ends the player's turn, as does taking [`MAX_ACTIONS_PER_TURN`].
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::game::{Action, Character, Game, Minion, Outcome, Side, Target};
+use crate::rng::Rng;
+use crate::{Card, CardInfo};
+
+/// Actions a player may take in one turn before the harness ends it for them.
+pub const MAX_ACTIONS_PER_TURN: usize = 64;
+
+/// Chooses actions for whichever player it is asked about.
+pub trait Player {
+    /// The next action for the player to act in `view`.
+    fn act(&mut self, view: &View) -> Action;
+}
+
+/// What the player to act can see of a game.
+#[derive(Debug, Clone)]
+pub struct View {
+    game: Game,
+}
+
+impl View {
+    pub fn new(game: &Game) -> View {
+        View {
+            game: game.seen_by(game.current_player()),
+        }
+    }
+
+    /// The game with the viewer's hidden information replaced by hidden cards.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The index of the player to act.
+    pub fn me(&self) -> usize {
+        self.game.current_player()
+    }
+
+    /// Every action the game would accept, with ones that lead to the same state as an earlier
+    /// one left out. Ending the turn comes last.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        successors(&self.game)
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect()
+    }
+}
+
+fn targets(game: &Game) -> Vec<Option<Target>> {
+    let mut targets = vec![None];
+    for (side, player) in [
+        (Side::Friendly, game.current_player()),
+        (Side::Enemy, 1 - game.current_player()),
+    ] {
+        targets.push(Some(Target {
+            side,
+            character: Character::Hero,
+        }));
+        targets.extend((0..game.player(player).board.len()).map(|i| {
+            Some(Target {
+                side,
+                character: Character::Minion(i),
+            })
+        }));
+    }
+    targets
+}
+
+/// The legal actions in `game` and the game after each, as for [`View::legal_actions`].
+fn successors(game: &Game) -> Vec<(Action, Game)> {
+    let me = game.player(game.current_player());
+    let enemy = game.player(1 - game.current_player());
+    let targets = targets(game);
+    let characters =
+        |board: usize| std::iter::once(Character::Hero).chain((0..board).map(Character::Minion));
+
+    let mut candidates = Vec::new();
+    for hand_index in 0..me.hand.len() {
+        candidates.extend(
+            targets
+                .iter()
+                .map(|&target| Action::PlayCard { hand_index, target }),
+        );
+    }
+    for attacker in characters(me.board.len()) {
+        candidates.extend(
+            characters(enemy.board.len()).map(|defender| Action::Attack { attacker, defender }),
+        );
+    }
+    for index in 0..me.locations.len() {
+        candidates.extend(
+            targets
+                .iter()
+                .map(|&target| Action::UseLocation { index, target }),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    let mut successors = Vec::new();
+    for action in candidates {
+        let mut next = game.clone();
+        if next.apply(action).is_ok() && seen.insert(next.state_hash()) {
+            successors.push((action, next));
+        }
+    }
+    let mut ended = game.clone();
+    if ended.apply(Action::EndTurn).is_ok() {
+        successors.push((Action::EndTurn, ended));
+    }
+    successors
+}
+
+/// How good `game` looks for `player`: hero health and armor, the stats of the minions and
+/// weapon in play and the cards in hand, less the same for the opponent. A won game is
+/// infinitely good and a lost one infinitely bad.
+pub fn evaluate(game: &Game, player: usize) -> f64 {
+    match game.outcome() {
+        Some(Outcome::Won(winner)) if winner == player => return f64::INFINITY,
+        Some(Outcome::Won(_)) => return f64::NEG_INFINITY,
+        Some(Outcome::Draw) => return 0.0,
+        None => {}
+    }
+    let side = |p: usize| {
+        let player = game.player(p);
+        let hero = &player.hero;
+        let weapon = hero.weapon.as_ref().map_or(0, |w| w.attack * w.durability);
+        let board: i32 = player
+            .board
+            .iter()
+            .map(|m| 2 * m.attack + 2 * m.health)
+            .sum();
+        (hero.health + hero.armor + weapon + board) as f64 + 1.5 * player.hand.len() as f64
+    };
+    side(player) - side(1 - player)
+}
+
+/// Picks uniformly among the legal actions, ending the turn included.
+#[derive(Debug, Clone)]
+pub struct RandomBot {
+    rng: Rng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Player for RandomBot {
+    fn act(&mut self, view: &View) -> Action {
+        let actions = view.legal_actions();
+        self.rng
+            .choose(&actions)
+            .copied()
+            .unwrap_or(Action::EndTurn)
+    }
+}
+
+/// Plays the most expensive card it can, then attacks: trading when a minion can be killed for
+/// less than it is worth, otherwise going face.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GreedyBot;
+
+impl Player for GreedyBot {
+    fn act(&mut self, view: &View) -> Action {
+        let game = view.game();
+        let me = view.me();
+        let successors = successors(game);
+        let best = |actions: &mut dyn Iterator<Item = &(Action, Game)>| {
+            actions
+                .max_by(|(_, a), (_, b)| evaluate(a, me).total_cmp(&evaluate(b, me)))
+                .map(|&(action, _)| action)
+        };
+
+        let hand = &game.player(me).hand;
+        let cost = |action: &Action| match action {
+            Action::PlayCard { hand_index, .. } => Some(hand[*hand_index].mana_cost()),
+            _ => None,
+        };
+        if let Some(top) = successors.iter().filter_map(|(a, _)| cost(a)).max() {
+            let plays = &mut successors.iter().filter(|(a, _)| cost(a) == Some(top));
+            return best(plays).unwrap_or(Action::EndTurn);
+        }
+
+        let attacks = || {
+            successors
+                .iter()
+                .filter(|(a, _)| matches!(a, Action::Attack { .. }))
+        };
+        let enemies = &game.player(1 - me).board;
+        let trades = &mut attacks().filter(|(action, next)| {
+            let Action::Attack {
+                attacker,
+                defender: Character::Minion(d),
+            } = *action
+            else {
+                return false;
+            };
+            // A good trade kills the defender and leaves the attacker alive, or is worth it.
+            let killed = next.player(1 - me).board.len() < enemies.len();
+            let value = |m: &Minion| m.attack + m.health;
+            let worth = match attacker {
+                Character::Minion(a) => {
+                    let survived = next.player(me).board.len() == game.player(me).board.len();
+                    survived || value(&enemies[d]) >= value(&game.player(me).board[a])
+                }
+                Character::Hero => true,
+            };
+            killed && worth
+        });
+        if let Some(trade) = best(trades) {
+            return trade;
+        }
+        let face = attacks().find(|(a, _)| {
+            matches!(
+                a,
+                Action::Attack {
+                    defender: Character::Hero,
+                    ..
+                }
+            )
+        });
+        if let Some(&(action, _)) = face {
+            return action;
+        }
+        best(&mut attacks()).unwrap_or(Action::EndTurn)
+    }
+}
+
+/// Tries every sequence of up to `depth` actions this turn and takes the first action of the one
+/// leading to the best-looking state by [`evaluate`], ending the turn when nothing improves it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LookaheadBot {
+    pub depth: usize,
+}
+
+impl Default for LookaheadBot {
+    fn default() -> LookaheadBot {
+        LookaheadBot { depth: 2 }
+    }
+}
+
+impl LookaheadBot {
+    /// The best score reachable from `game` in `depth` more actions without ending the turn.
+    fn search(game: &Game, me: usize, depth: usize) -> f64 {
+        let here = evaluate(game, me);
+        if depth == 0 || game.outcome().is_some() {
+            return here;
+        }
+        successors(game)
+            .into_iter()
+            .filter(|(action, _)| *action != Action::EndTurn)
+            .map(|(_, next)| LookaheadBot::search(&next, me, depth - 1))
+            .fold(here, f64::max)
+    }
+}
+
+impl Player for LookaheadBot {
+    fn act(&mut self, view: &View) -> Action {
+        let game = view.game();
+        let me = view.me();
+        let mut best = (evaluate(game, me), Action::EndTurn);
+        for (action, next) in successors(game) {
+            if action == Action::EndTurn {
+                continue;
+            }
+            let score = LookaheadBot::search(&next, me, self.depth.saturating_sub(1));
+            if score > best.0 {
+                best = (score, action);
+            }
+        }
+        best.1
+    }
+}
+
+/// Plays one game to the end, `players[i]` acting for the player with `decks[i]`.
+pub fn play_game(decks: [&[Card]; 2], players: [&mut dyn Player; 2], seed: u64) -> Game {
+    let mut game = Game::new(decks[0], decks[1], seed);
+    let mut actions = 0;
+    while game.outcome().is_none() {
+        let turn = game.turn();
+        let action = if actions < MAX_ACTIONS_PER_TURN {
+            players[game.current_player()].act(&View::new(&game))
+        } else {
+            Action::EndTurn
+        };
+        if game.apply(action).is_err() {
+            game.apply(Action::EndTurn)
+                .expect("a game in progress can always end the turn");
+        }
+        actions = if game.turn() == turn { actions + 1 } else { 0 };
+    }
+    game
+}
+
+/// Results of a series of games between two decks, from the first deck's point of view.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MatchReport {
+    pub games: u32,
+    /// Games won with the first and the second deck.
+    pub wins: [u32; 2],
+    pub draws: u32,
+}
+
+impl MatchReport {
+    pub fn record(&mut self, outcome: Outcome) {
+        self.games += 1;
+        match outcome {
+            Outcome::Won(player) => self.wins[player] += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+    }
+
+    /// The share of games won with deck `player` (0 or 1).
+    pub fn win_rate(&self, player: usize) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins[player] as f64 / self.games as f64
+    }
+
+    /// The 95% Wilson score interval for deck `player`'s win rate.
+    pub fn confidence_interval(&self, player: usize) -> (f64, f64) {
+        if self.games == 0 {
+            return (0.0, 1.0);
+        }
+        const Z: f64 = 1.96;
+        let n = self.games as f64;
+        let p = self.win_rate(player);
+        let scale = 1.0 + Z * Z / n;
+        let center = (p + Z * Z / (2.0 * n)) / scale;
+        let spread = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / scale;
+        ((center - spread).max(0.0), (center + spread).min(1.0))
+    }
+}
+
+impl fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} games, {} drawn", self.games, self.draws)?;
+        for player in 0..2 {
+            let (low, high) = self.confidence_interval(player);
+            writeln!(
+                f,
+                "Deck {}: {} wins, {:.1}% (95% CI {:.1}%-{:.1}%)",
+                player + 1,
+                self.wins[player],
+                self.win_rate(player) * 100.0,
+                low * 100.0,
+                high * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `games` games between the decks, with seeds counting up from `seed`.
+pub fn run_match(
+    decks: [&[Card]; 2],
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    games: u32,
+    seed: u64,
+) -> MatchReport {
+    let mut report = MatchReport::default();
+    for i in 0..games {
+        let game = play_game(
+            decks,
+            [&mut *first, &mut *second],
+            seed.wrapping_add(i as u64),
+        );
+        if let Some(outcome) = game.outcome() {
+            report.record(outcome);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::HIDDEN_ID;
+    use crate::game::test_cards::deck;
+
+    #[test]
+    fn bots_only_see_their_own_cards() {
+        let game = Game::new(&deck(2), &deck(2), 4);
+        let view = View::new(&game);
+        let me = view.me();
+        assert_eq!(view.game().player(me).hand, game.player(me).hand);
+        assert!(
+            view.game()
+                .player(1 - me)
+                .hand
+                .iter()
+                .all(|c| c.id() == HIDDEN_ID)
+        );
+        assert!(
+            view.game()
+                .player(me)
+                .deck
+                .iter()
+                .all(|c| c.id() == HIDDEN_ID)
+        );
+        assert_eq!(view.legal_actions().last(), Some(&Action::EndTurn));
+
+        let mut random = RandomBot::new(1);
+        let mut lookahead = LookaheadBot::default();
+        let game = play_game([&deck(2), &deck(2)], [&mut random, &mut lookahead], 3);
+        assert!(game.outcome().is_some());
+    }
+
+    #[test]
+    fn stronger_decks_win_more() {
+        let (strong, weak) = (deck(3), deck(1));
+        let report = run_match([&strong, &weak], &mut GreedyBot, &mut GreedyBot, 20, 10);
+        assert_eq!(report.games, 20);
+        assert_eq!(report.wins[0] + report.wins[1] + report.draws, 20);
+        assert!(report.win_rate(0) > 0.8);
+        let (low, high) = report.confidence_interval(0);
+        assert!(low < report.win_rate(0) && report.win_rate(0) <= high);
+        assert_eq!(
+            run_match([&strong, &weak], &mut GreedyBot, &mut GreedyBot, 20, 10),
+            report
+        );
+    }
+}
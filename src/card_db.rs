@@ -0,0 +1,554 @@
+//! Loads a card pool from a JSON array or a CSV export.
+//!
+//! Both formats use the field names of [`Card`]: `type`, `id`, `name`, `mana_cost`, `class`,
+//! `rarity`, `set` and `text`, plus `spell_school` for spells, `attack`, `health` and `minion_type`
+//! for minions, `attack` and `durability` for weapons, `durability` for locations and `armor` for
+//! heroes. Multi-class cards and multi-tribe minions separate their values with `|`, as in
+//! `Rogue|Warrior`; JSON may also use an array of strings.
+//!
+//! Names and text in other locales go in fields suffixed with the locale code, such as `name_deDE`
+//! and `text_koKR`, and are kept in the database's [`Translations`].
+//!
+//! A card's effect script, in the language of [`script`](crate::script), goes in its `script`
+//! field. Scripts are kept as written and compiled separately.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::json::{self, Value};
+use crate::{Card, CardInfo, CardKind, FlavorText, Locale, OwnedCard, Translations};
+
+pub const MAX_MANA_COST: u8 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadErrorKind {
+    Syntax(String),
+    MissingField,
+    UnknownValue(String),
+    InvalidNumber(String),
+    ManaCostOutOfRange(i64),
+    DuplicateId(u32),
+}
+
+/// Where loading failed: the 1-based line in the source and, if known, the offending field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    pub line: usize,
+    pub field: Option<String>,
+    pub kind: LoadErrorKind,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(field) = &self.field {
+            write!(f, ", field `{field}`")?;
+        }
+        match &self.kind {
+            LoadErrorKind::Syntax(msg) => write!(f, ": {msg}"),
+            LoadErrorKind::MissingField => write!(f, ": missing"),
+            LoadErrorKind::UnknownValue(v) => write!(f, ": unknown value \"{v}\""),
+            LoadErrorKind::InvalidNumber(v) => write!(f, ": \"{v}\" is not a whole number"),
+            LoadErrorKind::ManaCostOutOfRange(n) => {
+                write!(f, ": mana cost {n} is outside 0..={MAX_MANA_COST}")
+            }
+            LoadErrorKind::DuplicateId(id) => write!(f, ": id {id} is used by an earlier card"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CardDb {
+    cards: Vec<OwnedCard>,
+    by_id: HashMap<u32, usize>,
+    translations: Translations,
+    scripts: HashMap<u32, String>,
+}
+
+impl CardDb {
+    /// Builds a database from cards already in memory. If two share an id, the later card
+    /// replaces the earlier one in its place.
+    pub fn new(cards: Vec<OwnedCard>) -> CardDb {
+        let mut db = CardDb::default();
+        for card in cards {
+            match db.by_id.get(&card.id()) {
+                Some(&index) => db.cards[index] = card,
+                None => {
+                    db.by_id.insert(card.id(), db.cards.len());
+                    db.cards.push(card);
+                }
+            }
+        }
+        db
+    }
+
+    fn from_records(records: Vec<Record>) -> Result<CardDb, LoadError> {
+        let mut db = CardDb::default();
+        for record in records {
+            let card = record.to_card()?;
+            if db.by_id.insert(card.id(), db.cards.len()).is_some() {
+                let (_, line) = record.get("id")?;
+                return Err(record.error("id", line, LoadErrorKind::DuplicateId(card.id())));
+            }
+            record.add_translations(card.id(), &mut db.translations);
+            if let Ok((script, _)) = record.get("script") {
+                db.scripts.insert(card.id(), script.to_string());
+            }
+            db.cards.push(card);
+        }
+        Ok(db)
+    }
+
+    /// Parses a JSON array of card objects.
+    pub fn from_json(src: &str) -> Result<CardDb, LoadError> {
+        let root = json::parse(src).map_err(|e| LoadError {
+            line: e.line,
+            field: None,
+            kind: LoadErrorKind::Syntax(e.message),
+        })?;
+        let items = root.as_array().ok_or(LoadError {
+            line: root.line,
+            field: None,
+            kind: LoadErrorKind::Syntax("expected an array of cards".to_string()),
+        })?;
+
+        let mut records = Vec::with_capacity(items.len());
+        for item in items {
+            let Value::Object(fields) = &item.value else {
+                return Err(LoadError {
+                    line: item.line,
+                    field: None,
+                    kind: LoadErrorKind::Syntax("expected a card object".to_string()),
+                });
+            };
+            let mut record = Record {
+                line: item.line,
+                fields: Vec::with_capacity(fields.len()),
+            };
+            for (key, value) in fields {
+                let text = match &value.value {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Null => continue,
+                    Value::Array(items) if items.iter().all(|i| i.as_str().is_some()) => items
+                        .iter()
+                        .filter_map(|i| i.as_str())
+                        .collect::<Vec<_>>()
+                        .join("|"),
+                    _ => {
+                        return Err(LoadError {
+                            line: value.line,
+                            field: Some(key.clone()),
+                            kind: LoadErrorKind::Syntax("expected a string or number".to_string()),
+                        });
+                    }
+                };
+                record.fields.push((key.clone(), text, value.line));
+            }
+            records.push(record);
+        }
+        CardDb::from_records(records)
+    }
+
+    /// Parses a CSV export whose first row names the columns. Quoted fields may contain commas,
+    /// doubled quotes and line breaks.
+    pub fn from_csv(src: &str) -> Result<CardDb, LoadError> {
+        let mut rows = csv_rows(src)?.into_iter();
+        let Some((_, header)) = rows.next() else {
+            return Ok(CardDb::default());
+        };
+        let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+
+        let mut records = Vec::new();
+        for (line, row) in rows {
+            if row.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            if row.len() > header.len() {
+                return Err(LoadError {
+                    line,
+                    field: None,
+                    kind: LoadErrorKind::Syntax(format!(
+                        "expected {} columns, found {}",
+                        header.len(),
+                        row.len()
+                    )),
+                });
+            }
+            let fields = header
+                .iter()
+                .zip(row)
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(key, cell)| (key.clone(), cell, line))
+                .collect();
+            records.push(Record { line, fields });
+        }
+        CardDb::from_records(records)
+    }
+
+    /// Looks a card up by its DBF id.
+    pub fn by_id(&self, id: u32) -> Option<&OwnedCard> {
+        self.by_id.get(&id).map(|&index| &self.cards[index])
+    }
+
+    /// Names and text in locales other than enUS.
+    pub fn translations(&self) -> &Translations {
+        &self.translations
+    }
+
+    pub fn translations_mut(&mut self) -> &mut Translations {
+        &mut self.translations
+    }
+
+    /// The source of the effect script of the card with `id`, if it has one.
+    pub fn script(&self, id: u32) -> Option<&str> {
+        self.scripts.get(&id).map(String::as_str)
+    }
+
+    pub fn set_script(&mut self, id: u32, src: impl Into<String>) {
+        self.scripts.insert(id, src.into());
+    }
+
+    pub fn cards(&self) -> &[OwnedCard] {
+        &self.cards
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, OwnedCard> {
+        self.cards.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+impl Card<'_> {
+    /// The card as a JSON object with the fields [`CardDb::from_json`] reads.
+    pub fn to_json(&self) -> Value {
+        fn names<T: ToString>(items: &[T]) -> Value {
+            Value::array(items.iter().map(|item| Value::from(item.to_string())))
+        }
+        let mut fields = vec![
+            ("type", Value::from(self.kind().name())),
+            ("id", Value::from(self.id())),
+            ("name", Value::from(self.name())),
+            ("mana_cost", Value::from(self.mana_cost() as u32)),
+            ("class", names(self.classes())),
+            ("rarity", Value::from(self.rarity().name())),
+            ("set", Value::from(self.set().name())),
+        ];
+        if let Some(school) = self.spell_school() {
+            fields.push(("spell_school", Value::from(school.name())));
+        }
+        for (field, stat) in [
+            ("attack", self.attack()),
+            ("health", self.health()),
+            ("durability", self.durability()),
+            ("armor", self.armor()),
+        ] {
+            if let Some(stat) = stat {
+                fields.push((field, Value::from(stat as u32)));
+            }
+        }
+        if let Card::Minion { minion_types, .. } = self {
+            fields.push(("minion_type", names(minion_types)));
+        }
+        fields.push(("text", Value::from(self.text())));
+        Value::object(fields)
+    }
+}
+
+impl<'a> IntoIterator for &'a CardDb {
+    type Item = &'a OwnedCard;
+    type IntoIter = std::slice::Iter<'a, OwnedCard>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cards.iter()
+    }
+}
+
+/// One card's fields as strings, each with the line it came from.
+struct Record {
+    line: usize,
+    fields: Vec<(String, String, usize)>,
+}
+
+impl Record {
+    fn error(&self, field: &str, line: usize, kind: LoadErrorKind) -> LoadError {
+        LoadError {
+            line,
+            field: Some(field.to_string()),
+            kind,
+        }
+    }
+
+    fn get(&self, field: &str) -> Result<(&str, usize), LoadError> {
+        self.fields
+            .iter()
+            .find(|(key, _, _)| key == field)
+            .map(|(_, value, line)| (value.as_str(), *line))
+            .ok_or_else(|| self.error(field, self.line, LoadErrorKind::MissingField))
+    }
+
+    fn string(&self, field: &str) -> Result<Cow<'static, str>, LoadError> {
+        self.get(field)
+            .map(|(value, _)| Cow::Owned(value.to_string()))
+    }
+
+    fn enum_value<T>(&self, field: &str, parse: fn(&str) -> Option<T>) -> Result<T, LoadError> {
+        let (value, line) = self.get(field)?;
+        parse(value)
+            .ok_or_else(|| self.error(field, line, LoadErrorKind::UnknownValue(value.to_string())))
+    }
+
+    /// Reads a `|`-separated list. A missing field is an empty list.
+    fn enum_list<T>(&self, field: &str, parse: fn(&str) -> Option<T>) -> Result<Vec<T>, LoadError> {
+        let Ok((value, line)) = self.get(field) else {
+            return Ok(Vec::new());
+        };
+        value
+            .split('|')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                parse(item).ok_or_else(|| {
+                    self.error(field, line, LoadErrorKind::UnknownValue(item.to_string()))
+                })
+            })
+            .collect()
+    }
+
+    fn stat(&self, field: &str) -> Result<u8, LoadError> {
+        let (value, line) = self.get(field)?;
+        value
+            .trim()
+            .parse()
+            .map_err(|_| self.error(field, line, LoadErrorKind::InvalidNumber(value.to_string())))
+    }
+
+    fn id(&self) -> Result<u32, LoadError> {
+        let (value, line) = self.get("id")?;
+        value
+            .trim()
+            .parse()
+            .map_err(|_| self.error("id", line, LoadErrorKind::InvalidNumber(value.to_string())))
+    }
+
+    fn mana_cost(&self) -> Result<u8, LoadError> {
+        let (value, line) = self.get("mana_cost")?;
+        let cost: i64 = value.trim().parse().map_err(|_| {
+            self.error(
+                "mana_cost",
+                line,
+                LoadErrorKind::InvalidNumber(value.to_string()),
+            )
+        })?;
+        match u8::try_from(cost) {
+            Ok(cost) if cost <= MAX_MANA_COST => Ok(cost),
+            _ => Err(self.error("mana_cost", line, LoadErrorKind::ManaCostOutOfRange(cost))),
+        }
+    }
+
+    /// Reads the `name_<locale>` and `text_<locale>` fields of every non-enUS locale.
+    fn add_translations(&self, id: u32, translations: &mut Translations) {
+        for &locale in Locale::ALL.iter().filter(|&&l| l != Locale::EnUs) {
+            if let Ok((name, _)) = self.get(&format!("name_{locale}")) {
+                translations.set_name(id, locale, name);
+            }
+            if let Ok((text, _)) = self.get(&format!("text_{locale}")) {
+                translations.set_text(id, locale, text);
+            }
+        }
+    }
+
+    fn to_card(&self) -> Result<OwnedCard, LoadError> {
+        let kind = self.enum_value("type", |s| s.parse().ok())?;
+        let id = self.id()?;
+        let name = self.string("name")?;
+        let mana_cost = self.mana_cost()?;
+        let classes = self.enum_list("class", |s| s.parse().ok())?;
+        if classes.is_empty() {
+            return Err(self.error("class", self.line, LoadErrorKind::MissingField));
+        }
+        let rarity = self.enum_value("rarity", |s| s.parse().ok())?;
+        let set = self.enum_value("set", |s| s.parse().ok())?;
+        let text = self.string("text").unwrap_or_default();
+
+        Ok(match kind {
+            CardKind::Spell => Card::Spell {
+                id,
+                name,
+                mana_cost,
+                spell_school: self.enum_value("spell_school", |s| s.parse().ok())?,
+                classes: classes.clone(),
+                rarity,
+                set,
+                text,
+            },
+            CardKind::Minion => Card::Minion {
+                id,
+                name,
+                mana_cost,
+                attack: self.stat("attack")?,
+                health: self.stat("health")?,
+                minion_types: self.enum_list("minion_type", |s| s.parse().ok())?,
+                classes: classes.clone(),
+                rarity,
+                set,
+                text,
+            },
+            CardKind::Location => Card::Location {
+                id,
+                name,
+                mana_cost,
+                durability: self.stat("durability")?,
+                classes: classes.clone(),
+                rarity,
+                set,
+                text,
+            },
+            CardKind::Weapon => Card::Weapon {
+                id,
+                name,
+                mana_cost,
+                attack: self.stat("attack")?,
+                durability: self.stat("durability")?,
+                classes: classes.clone(),
+                rarity,
+                set,
+                text,
+            },
+            CardKind::Hero => Card::Hero {
+                id,
+                name,
+                mana_cost,
+                armor: self.stat("armor")?,
+                classes: classes.clone(),
+                rarity,
+                set,
+                text,
+            },
+        })
+    }
+}
+
+/// Splits CSV source into rows of cells, each paired with the line the row starts on.
+pub(crate) fn csv_rows(src: &str) -> Result<Vec<(usize, Vec<String>)>, LoadError> {
+    let mut rows = Vec::new();
+    let mut chars = src.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start_line = line;
+        let mut row = Vec::new();
+        let mut cell = String::new();
+        let mut quoted = false;
+
+        loop {
+            match chars.next() {
+                Some('"') if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        cell.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                Some('"') if cell.is_empty() => quoted = true,
+                Some(',') if !quoted => row.push(std::mem::take(&mut cell)),
+                Some('\r') if !quoted && chars.peek() == Some(&'\n') => {}
+                Some('\n') if !quoted => {
+                    line += 1;
+                    break;
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    cell.push(c);
+                }
+                None if quoted => {
+                    return Err(LoadError {
+                        line: start_line,
+                        field: None,
+                        kind: LoadErrorKind::Syntax("unterminated quoted field".to_string()),
+                    });
+                }
+                None => break,
+            }
+        }
+        row.push(cell);
+        rows.push((start_line, row));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinionType;
+
+    #[test]
+    fn loads_the_same_cards_from_json_and_csv() {
+        let json = r#"[
+            {"type": "minion", "id": 3, "name": "Edwin van Cleef", "mana_cost": 3, "class": "Rogue",
+             "rarity": "Legendary", "set": "Legacy", "attack": 2, "health": 2,
+             "minion_type": ["Pirate", "Undead"], "text": "Combo: Gain +2/+2."},
+            {"type": "spell", "id": 1052, "name": "Preparation", "mana_cost": 0, "class": "ROGUE",
+             "rarity": "EPIC", "set": "LEGACY", "spell_school": "shadow", "text": "The next spell costs (2) less."}
+        ]"#;
+        let csv = "type,id,name,mana_cost,class,rarity,set,attack,health,minion_type,spell_school,text\n\
+                   minion,3,Edwin van Cleef,3,Rogue,Legendary,Legacy,2,2,Pirate|Undead,,\"Combo: Gain +2/+2.\"\n\
+                   spell,1052,Preparation,0,rogue,epic,legacy,,,,Shadow,\"The next spell costs (2) less.\"\n";
+
+        let from_json = CardDb::from_json(json).unwrap();
+        let from_csv = CardDb::from_csv(csv).unwrap();
+
+        assert_eq!(from_json.len(), 2);
+        assert_eq!(from_json, from_csv);
+        let mut cards = from_csv.cards().to_vec();
+        cards.push(from_json.cards()[0].clone());
+        assert_eq!(CardDb::new(cards).cards(), from_csv.cards());
+        assert!(matches!(from_csv.by_id(1052), Some(Card::Spell { .. })));
+        assert_eq!(
+            from_json.by_id(3).unwrap().minion_types(),
+            [MinionType::Pirate, MinionType::Undead]
+        );
+    }
+
+    #[test]
+    fn reports_line_and_field_of_bad_values() {
+        let json = "[\n{\"type\": \"minion\", \"id\": 1, \"name\": \"X\", \"mana_cost\": 1,\n\"class\": \"Rouge\", \"minion_type\": \"Pirate\"}\n]";
+        let err = CardDb::from_json(json).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.field.as_deref(), Some("class"));
+        assert_eq!(err.kind, LoadErrorKind::UnknownValue("Rouge".to_string()));
+        let err = CardDb::from_json(&"[".repeat(300_000)).unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::Syntax(_)));
+
+        let csv = "type,id,name,mana_cost,class,rarity,set,attack,durability,armor\n\
+                   weapon,1,Kingsbane,1,Rogue,Legendary,Legacy,1,4,\n\
+                   hero,2,Big,99,Neutral|Rogue,Legendary,Core,,,5\n";
+        let err = CardDb::from_csv(csv).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.field.as_deref(), Some("mana_cost"));
+        assert_eq!(err.kind, LoadErrorKind::ManaCostOutOfRange(99));
+    }
+
+    #[test]
+    fn json_output_loads_back() {
+        let csv = "type,id,name,mana_cost,class,rarity,set,attack,health,minion_type,text\n\
+                   minion,3,Edwin \"Ed\" van Cleef,3,Rogue|Warrior,Legendary,Legacy,2,2,Pirate|Undead,\"Combo: Gain +2/+2.\"\n";
+        let db = CardDb::from_csv(csv).unwrap();
+        let json = Value::array(db.iter().map(Card::to_json));
+        assert_eq!(CardDb::from_json(&json.to_string()).unwrap(), db);
+        assert_eq!(CardDb::from_json(&format!("{json:#}")).unwrap(), db);
+    }
+}
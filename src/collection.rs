@@ -0,0 +1,326 @@
+//! A player's card collection: which cards they own, what a deck still needs and what crafting
+//! the missing cards costs.
+//!
+//! Collections are saved as CSV with an `id`, `finish` and `count` column. Imports may name cards
+//! instead of giving their id, and may leave out `finish` for normal copies. Golden and signature
+//! copies count towards a deck like normal ones.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::card_db::{LoadError, LoadErrorKind, csv_rows};
+use crate::diff::counts;
+use crate::names::normalize;
+use crate::stats::render_table;
+use crate::{Card, CardDb, CardInfo, CardSet, Class, Rarity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Finish {
+    Normal,
+    Golden,
+    Signature,
+}
+
+impl Rarity {
+    /// Dust needed to craft a normal copy; free cards can't be crafted.
+    pub fn craft_cost(self) -> Option<u32> {
+        match self {
+            Rarity::Free => None,
+            Rarity::Common => Some(40),
+            Rarity::Rare => Some(100),
+            Rarity::Epic => Some(400),
+            Rarity::Legendary => Some(1600),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Collection {
+    owned: HashMap<(u32, Finish), u32>,
+}
+
+impl Collection {
+    pub fn new() -> Collection {
+        Collection::default()
+    }
+
+    /// Adds `count` copies, saturating at `u32::MAX`.
+    pub fn add(&mut self, id: u32, finish: Finish, count: u32) {
+        let owned = self.owned.entry((id, finish)).or_default();
+        *owned = owned.saturating_add(count);
+    }
+
+    /// Copies of the card with `id` owned in every finish.
+    pub fn count(&self, id: u32) -> u32 {
+        self.owned
+            .iter()
+            .filter(|&(&(owned, _), _)| owned == id)
+            .fold(0u32, |total, (_, &count)| total.saturating_add(count))
+    }
+
+    pub fn count_of(&self, id: u32, finish: Finish) -> u32 {
+        self.owned.get(&(id, finish)).copied().unwrap_or(0)
+    }
+
+    /// The cards still needed to build `cards`, one entry per copy as [`Deck::expand`]
+    /// returns them, with the dust crafting them costs.
+    ///
+    /// [`Deck::expand`]: crate::Deck::expand
+    pub fn crafting_plan<'a>(&self, cards: &[Card<'a>]) -> CraftingPlan<'a> {
+        let mut plan = CraftingPlan::default();
+        for (card, needed) in counts(cards) {
+            let owned = self.count(card.id()).min(u8::MAX as u32) as u8;
+            if owned >= needed {
+                continue;
+            }
+            let missing = needed - owned;
+            match card.rarity().craft_cost() {
+                Some(cost) => {
+                    let dust = cost * missing as u32;
+                    match plan
+                        .dust_by_rarity
+                        .iter_mut()
+                        .find(|(r, _)| *r == card.rarity())
+                    {
+                        Some((_, total)) => *total += dust,
+                        None => plan.dust_by_rarity.push((card.rarity(), dust)),
+                    }
+                    plan.total_dust += dust;
+                }
+                None => plan.uncraftable.push((card.clone(), missing)),
+            }
+            plan.missing.push((card, missing));
+        }
+        plan.missing
+            .sort_by(|(a, _), (b, _)| (a.mana_cost(), a.name()).cmp(&(b.mana_cost(), b.name())));
+        plan.dust_by_rarity.sort();
+        plan
+    }
+
+    /// How much of `pool` is owned, per class and per set. A card is complete with as many copies
+    /// as a deck may hold.
+    pub fn completion(&self, pool: &[Card]) -> Completion {
+        let mut by_class: Vec<(Class, (u32, u32))> = Vec::new();
+        let mut by_set: Vec<(CardSet, (u32, u32))> = Vec::new();
+        fn tally<T: PartialEq>(totals: &mut Vec<(T, (u32, u32))>, key: T, owned: u32, limit: u32) {
+            match totals.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, (o, l))) => {
+                    *o += owned;
+                    *l += limit;
+                }
+                None => totals.push((key, (owned, limit))),
+            }
+        }
+        for card in pool {
+            let limit = card.rarity().copy_limit() as u32;
+            let owned = self.count(card.id()).min(limit);
+            for &class in card.classes() {
+                tally(&mut by_class, class, owned, limit);
+            }
+            tally(&mut by_set, card.set(), owned, limit);
+        }
+        let percent = |(owned, limit): (u32, u32)| 100.0 * owned as f64 / limit.max(1) as f64;
+        let mut completion = Completion {
+            by_class: by_class.into_iter().map(|(k, t)| (k, percent(t))).collect(),
+            by_set: by_set.into_iter().map(|(k, t)| (k, percent(t))).collect(),
+        };
+        completion
+            .by_class
+            .sort_by_key(|&(class, _)| Class::ALL.iter().position(|&c| c == class));
+        completion
+            .by_set
+            .sort_by_key(|&(set, _)| CardSet::ALL.iter().position(|&s| s == set));
+        completion
+    }
+
+    /// Reads a CSV with a `count` column, an `id` or `name` column and an optional `finish`
+    /// column. Names are matched ignoring case and punctuation.
+    pub fn from_csv(src: &str, db: &CardDb) -> Result<Collection, LoadError> {
+        let mut rows = csv_rows(src)?.into_iter();
+        let Some((_, header)) = rows.next() else {
+            return Ok(Collection::default());
+        };
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        let (id_col, name_col, finish_col) = (column("id"), column("name"), column("finish"));
+        let count_col = column("count");
+
+        let by_name: HashMap<String, u32> = db
+            .iter()
+            .map(|card| (normalize(card.name()), card.id()))
+            .collect();
+        let mut collection = Collection::default();
+        for (line, row) in rows {
+            if row.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            let cell = |col: Option<usize>| col.and_then(|c| row.get(c)).map(|s| s.trim());
+            let error = |field: &str, kind| LoadError {
+                line,
+                field: Some(field.to_string()),
+                kind,
+            };
+
+            let id = match (cell(id_col), cell(name_col)) {
+                (Some(id), _) if !id.is_empty() => id
+                    .parse()
+                    .ok()
+                    .filter(|&id| db.by_id(id).is_some())
+                    .ok_or_else(|| error("id", LoadErrorKind::UnknownValue(id.to_string())))?,
+                (_, Some(name)) if !name.is_empty() => *by_name
+                    .get(&normalize(name))
+                    .ok_or_else(|| error("name", LoadErrorKind::UnknownValue(name.to_string())))?,
+                _ => return Err(error("id", LoadErrorKind::MissingField)),
+            };
+            let finish = match cell(finish_col) {
+                Some(finish) if !finish.is_empty() => finish.parse().map_err(|_| {
+                    error("finish", LoadErrorKind::UnknownValue(finish.to_string()))
+                })?,
+                _ => Finish::Normal,
+            };
+            let count =
+                cell(count_col).ok_or_else(|| error("count", LoadErrorKind::MissingField))?;
+            let count = count
+                .parse()
+                .map_err(|_| error("count", LoadErrorKind::InvalidNumber(count.to_string())))?;
+            collection.add(id, finish, count);
+        }
+        Ok(collection)
+    }
+
+    /// The collection as CSV that [`Collection::from_csv`] reads back, sorted by id.
+    pub fn to_csv(&self) -> String {
+        let mut owned: Vec<_> = self.owned.iter().filter(|&(_, &count)| count > 0).collect();
+        owned.sort();
+        let mut out = String::from("id,finish,count\n");
+        for ((id, finish), count) in owned {
+            out.push_str(&format!("{id},{finish},{count}\n"));
+        }
+        out
+    }
+
+    pub fn load(path: impl AsRef<Path>, db: &CardDb) -> io::Result<Collection> {
+        let src = fs::read_to_string(path)?;
+        Collection::from_csv(&src, db).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CraftingPlan<'a> {
+    /// Cards short of the list, with how many copies are missing.
+    pub missing: Vec<(Card<'a>, u8)>,
+    /// Missing cards that can't be crafted, such as free cards not yet unlocked.
+    pub uncraftable: Vec<(Card<'a>, u8)>,
+    pub dust_by_rarity: Vec<(Rarity, u32)>,
+    pub total_dust: u32,
+}
+
+impl fmt::Display for CraftingPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.missing.is_empty() {
+            return writeln!(f, "all cards owned");
+        }
+        for (card, count) in &self.missing {
+            writeln!(f, "{count}x ({}) {}", card.mana_cost(), card.name())?;
+        }
+        let mut out = String::new();
+        let rows: Vec<(String, String)> = self
+            .dust_by_rarity
+            .iter()
+            .map(|(rarity, dust)| (rarity.to_string(), dust.to_string()))
+            .chain([("Total".to_string(), self.total_dust.to_string())])
+            .collect();
+        render_table(&mut out, "Dust", &rows);
+        f.write_str(&out)
+    }
+}
+
+/// Percentages of each class's and set's cards owned, leaving out those without cards.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Completion {
+    pub by_class: Vec<(Class, f64)>,
+    pub by_set: Vec<(CardSet, f64)>,
+}
+
+impl fmt::Display for Completion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn rows<T: fmt::Display>(percents: &[(T, f64)]) -> Vec<(String, String)> {
+            percents
+                .iter()
+                .map(|(value, percent)| (value.to_string(), format!("{percent:.1}%")))
+                .collect()
+        }
+        let mut out = String::new();
+        render_table(&mut out, "Class", &rows(&self.by_class));
+        render_table(&mut out, "Set", &rows(&self.by_set));
+        f.write_str(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARDS: &str = "type,id,name,mana_cost,class,rarity,set,attack,health,text\n\
+                         minion,3,Edwin van Cleef,3,Rogue,Legendary,Legacy,2,2,\n\
+                         minion,4,Patches the Pirate,1,Neutral,Legendary,Legacy,1,1,\n\
+                         minion,5,Southsea Deckhand,1,Neutral,Common,Legacy,2,1,\n\
+                         minion,6,Wisp,0,Neutral,Free,Core,1,1,\n";
+
+    #[test]
+    fn plans_crafting_and_round_trips() {
+        let db = CardDb::from_csv(CARDS).unwrap();
+        let collection = Collection::from_csv(
+            "name,count,finish\nedwin vancleef,1,golden\nSouthsea Deckhand,1,\n",
+            &db,
+        )
+        .unwrap();
+        assert_eq!(collection.count(3), 1);
+        assert_eq!(collection.count_of(3, Finish::Normal), 0);
+
+        let list: Vec<Card> = [3, 4, 5, 5, 6]
+            .iter()
+            .map(|&id| db.by_id(id).unwrap().borrowed())
+            .collect();
+        let plan = collection.crafting_plan(&list);
+        let missing: Vec<(u32, u8)> = plan.missing.iter().map(|(c, n)| (c.id(), *n)).collect();
+        assert_eq!(missing, [(6, 1), (4, 1), (5, 1)]);
+        assert_eq!(
+            plan.dust_by_rarity,
+            [(Rarity::Common, 40), (Rarity::Legendary, 1600)]
+        );
+        assert_eq!(plan.total_dust, 1640);
+        assert_eq!(plan.uncraftable.len(), 1);
+
+        let completion = collection.completion(db.cards());
+        assert_eq!(
+            completion.by_class,
+            [(Class::Rogue, 100.0), (Class::Neutral, 20.0)]
+        );
+
+        let saved = collection.to_csv();
+        assert_eq!(saved, "id,finish,count\n3,Golden,1\n5,Normal,1\n");
+        assert_eq!(Collection::from_csv(&saved, &db).unwrap(), collection);
+
+        let huge = Collection::from_csv(
+            "id,finish,count\n3,golden,4294967295\n3,golden,4294967295\n3,,1\n",
+            &db,
+        )
+        .unwrap();
+        assert_eq!(huge.count_of(3, Finish::Golden), u32::MAX);
+        assert_eq!(huge.count(3), u32::MAX);
+
+        let err = Collection::from_csv("id,count\n99,1\n", &db).unwrap_err();
+        assert_eq!((err.line, err.field.as_deref()), (2, Some("id")));
+    }
+}
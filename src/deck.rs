@@ -0,0 +1,146 @@
+//! A deck list: a hero, a format and a number of copies of each card.
+
+use std::fmt;
+
+use crate::{Card, CardDb, CardInfo, Class, OwnedCard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Wild,
+    Standard,
+    Classic,
+    Twist,
+}
+
+impl Format {
+    /// The number a deckstring uses for this format.
+    pub fn code(self) -> u64 {
+        match self {
+            Format::Wild => 1,
+            Format::Standard => 2,
+            Format::Classic => 3,
+            Format::Twist => 4,
+        }
+    }
+
+    pub fn from_code(code: u64) -> Option<Format> {
+        match code {
+            1 => Some(Format::Wild),
+            2 => Some(Format::Standard),
+            3 => Some(Format::Classic),
+            4 => Some(Format::Twist),
+            _ => None,
+        }
+    }
+}
+
+/// DBF ids of the original hero of each class.
+const DEFAULT_HEROES: [(Class, u32); 11] = [
+    (Class::DeathKnight, 78065),
+    (Class::DemonHunter, 56550),
+    (Class::Druid, 274),
+    (Class::Hunter, 31),
+    (Class::Mage, 637),
+    (Class::Paladin, 671),
+    (Class::Priest, 813),
+    (Class::Rogue, 930),
+    (Class::Shaman, 1066),
+    (Class::Warlock, 893),
+    (Class::Warrior, 7),
+];
+
+pub fn default_hero(class: Class) -> Option<u32> {
+    DEFAULT_HEROES
+        .iter()
+        .find(|(c, _)| *c == class)
+        .map(|&(_, id)| id)
+}
+
+/// Works out which class a hero id belongs to, preferring a `Card::Hero` in `db` and falling back
+/// to the original heroes.
+pub fn hero_class(db: &CardDb, hero: u32) -> Option<Class> {
+    let from_db = match db.by_id(hero) {
+        Some(card @ Card::Hero { .. }) => card.classes().first().copied(),
+        _ => None,
+    };
+    match from_db {
+        Some(class) if class != Class::Neutral => Some(class),
+        _ => DEFAULT_HEROES
+            .iter()
+            .find(|&&(_, id)| id == hero)
+            .map(|&(class, _)| class),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deck {
+    pub format: Format,
+    pub hero: u32,
+    pub class: Class,
+    pub cards: Vec<(OwnedCard, u8)>,
+}
+
+impl Deck {
+    /// An empty deck played by the class's original hero.
+    pub fn new(format: Format, class: Class) -> Deck {
+        Deck {
+            format,
+            hero: default_hero(class).unwrap_or(0),
+            class,
+            cards: Vec::new(),
+        }
+    }
+
+    /// Adds `count` copies of `card`, merging with any copies already in the deck.
+    pub fn add(&mut self, card: OwnedCard, count: u8) {
+        match self.cards.iter_mut().find(|(c, _)| c.id() == card.id()) {
+            Some((_, copies)) => *copies = copies.saturating_add(count),
+            None => self.cards.push((card, count)),
+        }
+    }
+
+    /// How many copies of the card with `id` the deck holds.
+    pub fn count(&self, id: u32) -> u8 {
+        self.cards
+            .iter()
+            .find(|(card, _)| card.id() == id)
+            .map_or(0, |&(_, count)| count)
+    }
+
+    /// Total number of cards, counting every copy.
+    pub fn len(&self) -> usize {
+        self.cards.iter().map(|&(_, count)| count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// One borrowed card per copy, for use with slice functions such as
+    /// [`filter_cards`](crate::filter_cards) and [`printd`](crate::printd).
+    pub fn expand(&self) -> Vec<Card<'_>> {
+        self.cards
+            .iter()
+            .flat_map(|(card, count)| std::iter::repeat_n(card, *count as usize))
+            .map(Card::borrowed)
+            .collect()
+    }
+}
+
+/// Writes the deck the way the game exports it: `#` comment lines listing the cards by cost,
+/// then the deckstring. [`Deck::from_deck_file`] reads it back.
+impl fmt::Display for Deck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "### {} deck", self.class)?;
+        writeln!(f, "# Class: {}", self.class)?;
+        writeln!(f, "# Format: {}", self.format)?;
+        writeln!(f, "#")?;
+        let mut cards: Vec<&(OwnedCard, u8)> = self.cards.iter().collect();
+        cards.sort_by(|(a, _), (b, _)| (a.mana_cost(), a.name()).cmp(&(b.mana_cost(), b.name())));
+        for (card, count) in cards {
+            writeln!(f, "# {count}x ({}) {}", card.mana_cost(), card.name())?;
+        }
+        writeln!(f, "#")?;
+        writeln!(f, "{}", self.to_deckstring())
+    }
+}
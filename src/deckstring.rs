@@ -0,0 +1,355 @@
+//! Encodes and decodes the base64 varint "deckstrings" the game uses to share decks.
+//!
+//! After a zero byte, a deckstring is a sequence of varints: the version, the format, the hero
+//! count and hero ids, then the cards held once, the cards held twice, and finally
+//! `(id, count)` pairs for any other count.
+
+use std::fmt;
+
+use crate::CardDb;
+use crate::deck::{self, Deck, Format};
+
+const VERSION: u64 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeckCodeError {
+    /// A character outside the base64 alphabet, at this byte offset of the deckstring.
+    InvalidBase64 {
+        position: usize,
+    },
+    UnexpectedEnd,
+    VarintOverflow,
+    InvalidHeader(u8),
+    UnsupportedVersion(u64),
+    UnknownFormat(u64),
+    HeroCount(u64),
+    UnknownHero(u32),
+    UnknownCard(u32),
+    InvalidCount {
+        id: u32,
+        count: u64,
+    },
+    TrailingBytes,
+}
+
+impl fmt::Display for DeckCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeckCodeError::InvalidBase64 { position } => {
+                write!(f, "invalid base64 character at position {position}")
+            }
+            DeckCodeError::UnexpectedEnd => write!(f, "deckstring ends unexpectedly"),
+            DeckCodeError::VarintOverflow => write!(f, "number too large"),
+            DeckCodeError::InvalidHeader(byte) => write!(f, "invalid header byte {byte}"),
+            DeckCodeError::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            DeckCodeError::UnknownFormat(v) => write!(f, "unknown format {v}"),
+            DeckCodeError::HeroCount(n) => write!(f, "expected 1 hero, found {n}"),
+            DeckCodeError::UnknownHero(id) => write!(f, "unknown hero id {id}"),
+            DeckCodeError::UnknownCard(id) => write!(f, "unknown card id {id}"),
+            DeckCodeError::InvalidCount { id, count } => {
+                write!(f, "invalid count {count} for card id {id}")
+            }
+            DeckCodeError::TrailingBytes => write!(f, "unexpected data after the card lists"),
+        }
+    }
+}
+
+impl std::error::Error for DeckCodeError {}
+
+impl Deck {
+    /// Decodes `code`, resolving the hero and every card id through `db`.
+    pub fn from_deckstring(code: &str, db: &CardDb) -> Result<Deck, DeckCodeError> {
+        let bytes = decode_base64(code.trim())?;
+        let mut reader = Reader {
+            bytes: &bytes,
+            pos: 0,
+        };
+
+        let header = reader.byte()?;
+        if header != 0 {
+            return Err(DeckCodeError::InvalidHeader(header));
+        }
+        let version = reader.varint()?;
+        if version != VERSION {
+            return Err(DeckCodeError::UnsupportedVersion(version));
+        }
+        let format = reader.varint()?;
+        let format = Format::from_code(format).ok_or(DeckCodeError::UnknownFormat(format))?;
+        let heroes = reader.varint()?;
+        if heroes != 1 {
+            return Err(DeckCodeError::HeroCount(heroes));
+        }
+        let hero = reader.id()?;
+        let class = deck::hero_class(db, hero).ok_or(DeckCodeError::UnknownHero(hero))?;
+
+        let mut deck = Deck {
+            format,
+            hero,
+            class,
+            cards: Vec::new(),
+        };
+        let mut add = |id: u32, count: u64| {
+            let card = db.by_id(id).ok_or(DeckCodeError::UnknownCard(id))?;
+            let copies = u8::try_from(count)
+                .ok()
+                .filter(|&c| c > 0)
+                .ok_or(DeckCodeError::InvalidCount { id, count })?;
+            deck.add(card.clone(), copies);
+            Ok(())
+        };
+        for count in [1, 2] {
+            for _ in 0..reader.varint()? {
+                add(reader.id()?, count)?;
+            }
+        }
+        for _ in 0..reader.varint()? {
+            let id = reader.id()?;
+            add(id, reader.varint()?)?;
+        }
+
+        // Newer clients append a sideboard flag. A flag of 1 is followed by the cards set aside
+        // for cards such as E.T.C., each naming the card that owns it, in the same three lists.
+        // They aren't part of the deck, so they are read and skipped.
+        let sideboards = match reader.bytes.get(reader.pos) {
+            None => false,
+            Some(0 | 1) => reader.byte()? == 1,
+            Some(_) => return Err(DeckCodeError::TrailingBytes),
+        };
+        if sideboards {
+            for counted in [false, false, true] {
+                for _ in 0..reader.varint()? {
+                    reader.id()?;
+                    if counted {
+                        reader.varint()?;
+                    }
+                    reader.id()?;
+                }
+            }
+        }
+        if reader.pos == reader.bytes.len() {
+            Ok(deck)
+        } else {
+            Err(DeckCodeError::TrailingBytes)
+        }
+    }
+
+    /// Reads a deck file as the game exports it: lines starting with `#` are comments and the
+    /// first other non-blank line is the deckstring.
+    pub fn from_deck_file(src: &str, db: &CardDb) -> Result<Deck, DeckCodeError> {
+        let code = src
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or("");
+        Deck::from_deckstring(code, db)
+    }
+
+    pub fn to_deckstring(&self) -> String {
+        let mut bytes = vec![0];
+        write_varint(&mut bytes, VERSION);
+        write_varint(&mut bytes, self.format.code());
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, self.hero as u64);
+
+        let mut ids: Vec<(u32, u8)> = self
+            .cards
+            .iter()
+            .map(|(card, count)| (card.id(), *count))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        ids.sort_unstable();
+
+        for copies in [1, 2] {
+            let group: Vec<u32> = ids
+                .iter()
+                .filter(|&&(_, count)| count == copies)
+                .map(|&(id, _)| id)
+                .collect();
+            write_varint(&mut bytes, group.len() as u64);
+            for id in group {
+                write_varint(&mut bytes, id as u64);
+            }
+        }
+        let rest: Vec<(u32, u8)> = ids.into_iter().filter(|&(_, count)| count > 2).collect();
+        write_varint(&mut bytes, rest.len() as u64);
+        for (id, count) in rest {
+            write_varint(&mut bytes, id as u64);
+            write_varint(&mut bytes, count as u64);
+        }
+
+        encode_base64(&bytes)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, DeckCodeError> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or(DeckCodeError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, DeckCodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DeckCodeError::VarintOverflow)
+    }
+
+    fn id(&mut self) -> Result<u32, DeckCodeError> {
+        u32::try_from(self.varint()?).map_err(|_| DeckCodeError::VarintOverflow)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64; trailing `=` padding is optional.
+fn decode_base64(text: &str) -> Result<Vec<u8>, DeckCodeError> {
+    let data = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for (position, c) in data.bytes().enumerate() {
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(DeckCodeError::InvalidBase64 { position })?;
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    if bits >= 6 {
+        return Err(DeckCodeError::InvalidBase64 {
+            position: data.len(),
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, CardSet, Class, Rarity};
+    use std::borrow::Cow;
+
+    fn db() -> CardDb {
+        let weapon = |id| Card::Weapon {
+            id,
+            name: Cow::Owned(format!("Weapon {id}")),
+            mana_cost: 1,
+            attack: 2,
+            durability: 2,
+            classes: vec![Class::Rogue],
+            rarity: Rarity::Common,
+            set: CardSet::Legacy,
+            text: Cow::Borrowed(""),
+        };
+        CardDb::new(vec![
+            weapon(48353),
+            weapon(3),
+            weapon(200_000),
+            weapon(1052),
+        ])
+    }
+
+    #[test]
+    fn deckstrings_round_trip() {
+        let db = db();
+        let mut deck = Deck::new(Format::Standard, Class::Rogue);
+        deck.add(db.by_id(48353).unwrap().clone(), 1);
+        deck.add(db.by_id(3).unwrap().clone(), 2);
+        deck.add(db.by_id(200_000).unwrap().clone(), 2);
+        deck.add(db.by_id(1052).unwrap().clone(), 3);
+
+        let code = deck.to_deckstring();
+        let decoded = Deck::from_deckstring(&code, &db).unwrap();
+
+        assert_eq!(decoded.hero, 930);
+        assert_eq!(decoded.class, Class::Rogue);
+        assert_eq!(decoded.format, Format::Standard);
+        assert_eq!(decoded.len(), 8);
+        assert_eq!(decoded.count(1052), 3);
+        assert_eq!(decoded.to_deckstring(), code);
+
+        // The same deck with a sideboard: one copy of weapon 1052 and three of weapon 200000,
+        // both owned by weapon 200000, which decodes to the deck without them.
+        let mut bytes = decode_base64(&code).unwrap();
+        for value in [1, 1, 1052, 200_000, 0, 1, 200_000, 3, 200_000] {
+            write_varint(&mut bytes, value);
+        }
+        assert_eq!(
+            Deck::from_deckstring(&encode_base64(&bytes), &db).as_ref(),
+            Ok(&decoded)
+        );
+        bytes.push(0);
+        assert_eq!(
+            Deck::from_deckstring(&encode_base64(&bytes), &db),
+            Err(DeckCodeError::TrailingBytes)
+        );
+
+        let file = deck.to_string();
+        assert!(file.contains("# 3x (1) Weapon 1052\n"));
+        assert_eq!(Deck::from_deck_file(&file, &db), Ok(decoded));
+    }
+
+    #[test]
+    fn rejects_bad_base64_and_unknown_ids() {
+        let db = db();
+        assert_eq!(
+            Deck::from_deckstring("AAE*", &db),
+            Err(DeckCodeError::InvalidBase64 { position: 3 })
+        );
+
+        let mut deck = Deck::new(Format::Wild, Class::Rogue);
+        deck.add(db.by_id(3).unwrap().clone(), 1);
+        let code = deck.to_deckstring();
+        assert_eq!(
+            Deck::from_deckstring(&code, &CardDb::default()),
+            Err(DeckCodeError::UnknownCard(3))
+        );
+    }
+}
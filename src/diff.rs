@@ -0,0 +1,155 @@
+//! Comparing two deck lists: which cards came in, which went out, which changed count, and how
+//! the mana curve moved.
+
+use std::fmt;
+
+use crate::deck::Deck;
+use crate::stats::CURVE_BUCKETS;
+use crate::{Card, CardInfo};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeckDiff<'a> {
+    /// Cards only in the new list, with their copy count.
+    pub added: Vec<(Card<'a>, u8)>,
+    /// Cards only in the old list, with their copy count.
+    pub removed: Vec<(Card<'a>, u8)>,
+    /// Cards in both lists with a different count, as `(card, before, after)`.
+    pub changed: Vec<(Card<'a>, u8, u8)>,
+    /// New minus old number of cards at each cost, bucketed like [`DeckStats`](crate::DeckStats).
+    pub curve_delta: [i32; CURVE_BUCKETS],
+}
+
+impl DeckDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Counts copies by card id, keeping the first copy seen of each card.
+pub(crate) fn counts<'a>(cards: &[Card<'a>]) -> Vec<(Card<'a>, u8)> {
+    let mut counts: Vec<(Card<'a>, u8)> = Vec::new();
+    for card in cards {
+        match counts.iter_mut().find(|(c, _)| c.id() == card.id()) {
+            Some((_, count)) => *count = count.saturating_add(1),
+            None => counts.push((card.clone(), 1)),
+        }
+    }
+    counts
+}
+
+/// Compares two lists holding one entry per copy, as [`Deck::expand`] returns them.
+pub fn diff_cards<'a>(old: &[Card<'a>], new: &[Card<'a>]) -> DeckDiff<'a> {
+    let before = counts(old);
+    let after = counts(new);
+    let count_in = |list: &[(Card, u8)], id| {
+        list.iter()
+            .find(|(card, _)| card.id() == id)
+            .map_or(0, |&(_, count)| count)
+    };
+
+    let mut diff = DeckDiff::default();
+    for (card, count) in &after {
+        match count_in(&before, card.id()) {
+            0 => diff.added.push((card.clone(), *count)),
+            old if old != *count => diff.changed.push((card.clone(), old, *count)),
+            _ => {}
+        }
+    }
+    for (card, count) in &before {
+        if count_in(&after, card.id()) == 0 {
+            diff.removed.push((card.clone(), *count));
+        }
+    }
+    let by_cost = |a: &Card, b: &Card| (a.mana_cost(), a.name()).cmp(&(b.mana_cost(), b.name()));
+    diff.added.sort_by(|(a, _), (b, _)| by_cost(a, b));
+    diff.removed.sort_by(|(a, _), (b, _)| by_cost(a, b));
+    diff.changed.sort_by(|(a, _, _), (b, _, _)| by_cost(a, b));
+
+    for (cards, sign) in [(new, 1), (old, -1)] {
+        for card in cards {
+            diff.curve_delta[(card.mana_cost() as usize).min(CURVE_BUCKETS - 1)] += sign;
+        }
+    }
+    diff
+}
+
+impl Deck {
+    /// What changed going from this deck to `other`.
+    pub fn diff<'a>(&'a self, other: &'a Deck) -> DeckDiff<'a> {
+        diff_cards(&self.expand(), &other.expand())
+    }
+}
+
+/// One line per change: `+` for added cards, `-` for removed ones and `~` for count changes,
+/// followed by the non-zero mana-curve deltas.
+impl fmt::Display for DeckDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (card, count) in &self.added {
+            writeln!(f, "+ {count}x {card}")?;
+        }
+        for (card, count) in &self.removed {
+            writeln!(f, "- {count}x {card}")?;
+        }
+        for (card, before, after) in &self.changed {
+            writeln!(f, "~ {before}x -> {after}x {card}")?;
+        }
+        let curve: Vec<String> = self
+            .curve_delta
+            .iter()
+            .enumerate()
+            .filter(|&(_, &delta)| delta != 0)
+            .map(|(cost, delta)| {
+                let plus = if cost == CURVE_BUCKETS - 1 { "+" } else { "" };
+                format!("{cost}{plus}: {delta:+}")
+            })
+            .collect();
+        if !curve.is_empty() {
+            writeln!(f, "Mana curve: {}", curve.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardSet, Class, Format, Rarity, SpellSchool};
+    use std::borrow::Cow;
+
+    fn spell(id: u32, mana_cost: u8) -> Card<'static> {
+        Card::Spell {
+            id,
+            name: Cow::Owned(format!("Spell {id}")),
+            mana_cost,
+            spell_school: SpellSchool::Shadow,
+            classes: vec![Class::Rogue],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(""),
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_cards() {
+        let old = vec![spell(1, 1), spell(1, 1), spell(2, 2), spell(3, 9)];
+        let new = vec![spell(1, 1), spell(3, 9), spell(4, 4), spell(4, 4)];
+
+        let diff = diff_cards(&old, &new);
+        assert_eq!(diff.added, vec![(spell(4, 4), 2)]);
+        assert_eq!(diff.removed, vec![(spell(2, 2), 1)]);
+        assert_eq!(diff.changed, vec![(spell(1, 1), 2, 1)]);
+        assert_eq!(diff.curve_delta, [0, -1, -1, 0, 2, 0, 0, 0]);
+
+        let text = diff.to_string();
+        assert!(text.starts_with("+ 2x Name: Spell 4 |"));
+        assert!(text.ends_with("Mana curve: 1: -1, 2: -1, 4: +2\n"));
+
+        let mut a = Deck::new(Format::Wild, Class::Rogue);
+        let mut b = a.clone();
+        a.add(spell(1, 1), 2);
+        b.add(spell(1, 1), 2);
+        assert!(a.diff(&b).is_empty());
+        b.add(spell(2, 2), 1);
+        assert_eq!(a.diff(&b).added, vec![(spell(2, 2), 1)]);
+    }
+}
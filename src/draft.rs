@@ -0,0 +1,215 @@
+//! Arena drafts: thirty picks, each from an offer of three cards.
+//!
+//! Offers only hold cards of the hero's class and neutral cards. Each offer first rolls a rarity
+//! by [`RARITY_WEIGHTS`], then shows three different cards of that rarity, with free cards
+//! counting as common. Arena has no copy limit, so the same card may be picked again.
+
+use crate::deck::{Deck, Format};
+use crate::rng::Rng;
+use crate::stats::CURVE_BUCKETS;
+use crate::{Card, CardInfo, Class, OwnedCard, Rarity};
+
+pub const DRAFT_PICKS: usize = 30;
+pub const OFFER_SIZE: usize = 3;
+
+/// How often each rarity is offered, out of the total weight.
+pub const RARITY_WEIGHTS: [(Rarity, u32); 4] = [
+    (Rarity::Common, 730),
+    (Rarity::Rare, 200),
+    (Rarity::Epic, 55),
+    (Rarity::Legendary, 15),
+];
+
+/// Chooses a card from each offer.
+pub trait PickStrategy {
+    /// The index in `offer` of the card to take, given the picks in `deck` so far.
+    fn pick(&mut self, offer: &[&OwnedCard], deck: &Deck) -> usize;
+}
+
+/// Number of picks at each cost [`CurveScorer`] aims for, bucketed like the mana curve.
+const TARGET_CURVE: [usize; CURVE_BUCKETS] = [1, 5, 7, 5, 4, 3, 2, 3];
+
+/// The default strategy: takes the card with the best stats for its cost and keyword count,
+/// favouring costs the deck is short of.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurveScorer;
+
+impl CurveScorer {
+    pub fn score(&self, card: &Card, deck: &Deck) -> f64 {
+        let cost = card.mana_cost() as f64;
+        let stats = match card {
+            Card::Minion { attack, health, .. } => {
+                (*attack as f64 + *health as f64) / (2.0 * cost + 1.0)
+            }
+            Card::Weapon {
+                attack, durability, ..
+            } => (*attack as f64 * *durability as f64) / (2.0 * cost + 1.0),
+            _ => 1.0,
+        };
+        let keywords = 0.25 * card.parsed_text().keywords().len() as f64;
+
+        let bucket = (card.mana_cost() as usize).min(CURVE_BUCKETS - 1);
+        let at_cost: usize = deck
+            .cards
+            .iter()
+            .filter(|(c, _)| (c.mana_cost() as usize).min(CURVE_BUCKETS - 1) == bucket)
+            .map(|&(_, count)| count as usize)
+            .sum();
+        let curve = if at_cost < TARGET_CURVE[bucket] {
+            0.5
+        } else {
+            -0.5 * (at_cost + 1 - TARGET_CURVE[bucket]) as f64
+        };
+        stats + keywords + curve
+    }
+}
+
+impl PickStrategy for CurveScorer {
+    fn pick(&mut self, offer: &[&OwnedCard], deck: &Deck) -> usize {
+        let mut best = 0;
+        for (i, card) in offer.iter().enumerate() {
+            if self.score(card, deck) > self.score(offer[best], deck) {
+                best = i;
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Draft<'p> {
+    candidates: Vec<&'p OwnedCard>,
+    rng: Rng,
+    deck: Deck,
+    offer: Vec<&'p OwnedCard>,
+}
+
+impl<'p> Draft<'p> {
+    /// Starts a draft for `class` from the cards in `pool`.
+    pub fn new(pool: &'p [OwnedCard], class: Class, seed: u64) -> Draft<'p> {
+        let candidates = pool
+            .iter()
+            .filter(|card| {
+                card.classes()
+                    .iter()
+                    .any(|&c| c == class || c == Class::Neutral)
+            })
+            .collect();
+        Draft {
+            candidates,
+            rng: Rng::new(seed),
+            deck: Deck::new(Format::Wild, class),
+            offer: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.deck.len() >= DRAFT_PICKS
+    }
+
+    /// The cards on offer for the next pick, rolling a new offer if needed. Empty once the draft
+    /// is done or if no card fits the class.
+    pub fn offer(&mut self) -> &[&'p OwnedCard] {
+        if self.offer.is_empty() && !self.is_done() {
+            let rarity = self.roll_rarity();
+            let mut options: Vec<&OwnedCard> = self
+                .candidates
+                .iter()
+                .copied()
+                .filter(|card| card.rarity().max(Rarity::Common) == rarity)
+                .collect();
+            if options.len() < OFFER_SIZE {
+                options = self.candidates.clone();
+            }
+            self.rng.shuffle(&mut options);
+            options.truncate(OFFER_SIZE);
+            self.offer = options;
+        }
+        &self.offer
+    }
+
+    /// Takes the card at `index` of the current offer, or returns `None` if there isn't one.
+    pub fn choose(&mut self, index: usize) -> Option<&'p OwnedCard> {
+        let card = *self.offer.get(index)?;
+        self.offer.clear();
+        self.deck.add(card.clone(), 1);
+        Some(card)
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// Makes the remaining picks with `strategy` and returns the drafted deck. An index past the
+    /// end of the offer takes the last card.
+    pub fn run(mut self, strategy: &mut impl PickStrategy) -> Deck {
+        while !self.offer().is_empty() {
+            let index = strategy.pick(&self.offer, &self.deck);
+            self.choose(index.min(self.offer.len() - 1));
+        }
+        self.deck
+    }
+
+    fn roll_rarity(&mut self) -> Rarity {
+        let total: u32 = RARITY_WEIGHTS.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = self.rng.below(total as usize) as u32;
+        for (rarity, weight) in RARITY_WEIGHTS {
+            if roll < weight {
+                return rarity;
+            }
+            roll -= weight;
+        }
+        Rarity::Common
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CardSet;
+    use std::borrow::Cow;
+
+    fn pool() -> Vec<OwnedCard> {
+        let classes = [Class::Rogue, Class::Neutral, Class::Mage];
+        let rarities = [Rarity::Free, Rarity::Rare, Rarity::Epic, Rarity::Legendary];
+        (0..120)
+            .map(|i| Card::Minion {
+                id: i,
+                name: Cow::Owned(format!("Minion {i}")),
+                mana_cost: (i % 8) as u8,
+                attack: (i % 5) as u8,
+                health: (i % 7) as u8 + 1,
+                minion_types: Vec::new(),
+                classes: vec![classes[i as usize % 3]],
+                rarity: rarities[i as usize % 4],
+                set: CardSet::Core,
+                text: Cow::Borrowed(""),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drafts_class_cards_repeatably() {
+        let pool = pool();
+        let mut draft = Draft::new(&pool, Class::Rogue, 5);
+        let offer = draft.offer().to_vec();
+        assert_eq!(offer.len(), OFFER_SIZE);
+        let rarity = |card: &OwnedCard| card.rarity().max(Rarity::Common);
+        assert!(offer.iter().all(|c| rarity(c) == rarity(offer[0])));
+        assert_ne!(offer[0].id(), offer[1].id());
+        assert_eq!(draft.choose(OFFER_SIZE), None);
+        assert_eq!(draft.choose(1), Some(offer[1]));
+
+        let deck = draft.run(&mut CurveScorer);
+        assert_eq!(deck.len(), DRAFT_PICKS);
+        assert!(
+            deck.cards
+                .iter()
+                .all(|(card, _)| card.class() != Class::Mage)
+        );
+        let mut again = Draft::new(&pool, Class::Rogue, 5);
+        again.offer();
+        again.choose(1);
+        assert_eq!(again.run(&mut CurveScorer), deck);
+    }
+}
@@ -0,0 +1,1239 @@
+//! A deterministic two-player game engine.
+//!
+//! Heroes start at 30 health, mana crystals ramp to 10, hands hold 10 cards and boards hold 7
+//! minions and locations. Drawing from an empty deck deals increasing fatigue damage. Card effects
+//! come from [`CardText`]: damage, healing, draw, armor, buffs, tokens with printed stats,
+//! granted keywords, Freeze, Silence and temporary mana are carried out; other effects are
+//! ignored. Effects that need a target use the one given with the action, or a sensible default.
+//!
+//! All randomness comes from a seeded [`Rng`], so the same decks, seed and actions always produce
+//! the same game and the same [`Event`] log.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::keywords::{Ability, CardText, Effect, Keyword};
+use crate::rng::Rng;
+use crate::{Card, CardInfo, CardSet, Class, OwnedCard, Rarity, SpellSchool};
+
+pub const STARTING_HEALTH: i32 = 30;
+pub const MAX_MANA: u8 = 10;
+pub const MAX_HAND: usize = 10;
+pub const MAX_BOARD: usize = 7;
+/// The game is a draw once this many turns have been played.
+pub const MAX_TURNS: u32 = 89;
+pub const COIN_ID: u32 = 1746;
+pub const HIDDEN_ID: u32 = 0;
+
+/// The card the player going second starts with.
+pub fn the_coin() -> OwnedCard {
+    Card::Spell {
+        id: COIN_ID,
+        name: Cow::Borrowed("The Coin"),
+        mana_cost: 0,
+        spell_school: SpellSchool::General,
+        classes: vec![Class::Neutral],
+        rarity: Rarity::Free,
+        set: CardSet::Core,
+        text: Cow::Borrowed("Gain 1 Mana Crystal this turn only."),
+    }
+}
+
+/// Stands in for a card its viewer can't see in [`Game::seen_by`]. It costs more mana than any
+/// player can have, so it is never played.
+pub fn hidden_card() -> OwnedCard {
+    Card::Spell {
+        id: HIDDEN_ID,
+        name: Cow::Borrowed("Hidden Card"),
+        mana_cost: u8::MAX,
+        spell_school: SpellSchool::General,
+        classes: vec![Class::Neutral],
+        rarity: Rarity::Free,
+        set: CardSet::Core,
+        text: Cow::Borrowed(""),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Friendly,
+    Enemy,
+}
+
+/// A hero or a minion, by its index on its owner's board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Character {
+    Hero,
+    Minion(usize),
+}
+
+/// A character seen from the acting player's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub side: Side,
+    pub character: Character,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    PlayCard {
+        hand_index: usize,
+        target: Option<Target>,
+    },
+    /// A friendly `attacker` attacks an enemy `defender`.
+    Attack {
+        attacker: Character,
+        defender: Character,
+    },
+    UseLocation {
+        index: usize,
+        target: Option<Target>,
+    },
+    EndTurn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Won(usize),
+    Draw,
+}
+
+/// Something that happened, with players given by index (0 or 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    GameStarted {
+        first: usize,
+    },
+    TurnStarted {
+        player: usize,
+        turn: u32,
+    },
+    Drew {
+        player: usize,
+        card: u32,
+    },
+    Burned {
+        player: usize,
+        card: u32,
+    },
+    Fatigue {
+        player: usize,
+        damage: i32,
+    },
+    Played {
+        player: usize,
+        card: u32,
+    },
+    Summoned {
+        player: usize,
+        card: u32,
+    },
+    Equipped {
+        player: usize,
+        card: u32,
+    },
+    Attacked {
+        player: usize,
+        attacker: Character,
+        defender: Character,
+    },
+    Damaged {
+        player: usize,
+        character: Character,
+        amount: i32,
+    },
+    Healed {
+        player: usize,
+        character: Character,
+        amount: i32,
+    },
+    ArmorGained {
+        player: usize,
+        amount: i32,
+    },
+    Died {
+        player: usize,
+        card: u32,
+    },
+    WeaponBroke {
+        player: usize,
+        card: u32,
+    },
+    LocationUsed {
+        player: usize,
+        card: u32,
+    },
+    TurnEnded {
+        player: usize,
+    },
+    GameOver {
+        outcome: Outcome,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionError {
+    GameOver,
+    NoSuchCard(usize),
+    NotEnoughMana { cost: u8, available: u8 },
+    BoardFull,
+    InvalidTarget,
+    CannotAttack,
+    MustAttackTaunt,
+    LocationNotReady,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::GameOver => write!(f, "the game is over"),
+            ActionError::NoSuchCard(i) => write!(f, "no card at position {i}"),
+            ActionError::NotEnoughMana { cost, available } => {
+                write!(f, "costs {cost} mana but only {available} is available")
+            }
+            ActionError::BoardFull => write!(f, "the board is full"),
+            ActionError::InvalidTarget => write!(f, "invalid target"),
+            ActionError::CannotAttack => write!(f, "that character cannot attack"),
+            ActionError::MustAttackTaunt => write!(f, "a minion with Taunt must be attacked"),
+            ActionError::LocationNotReady => write!(f, "that location cannot be used yet"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Minion {
+    pub card: u32,
+    pub name: String,
+    pub attack: i32,
+    pub health: i32,
+    pub max_health: i32,
+    pub keywords: Vec<Keyword>,
+    pub deathrattle: Vec<Effect>,
+    pub attacks_made: u8,
+    /// Summoned this turn, so it cannot attack yet without Charge or Rush.
+    pub sleeping: bool,
+    pub frozen: bool,
+}
+
+impl Minion {
+    fn from_card(card: &Card) -> Minion {
+        let text = card.parsed_text();
+        let health = card.health().unwrap_or(1) as i32;
+        Minion {
+            card: card.id(),
+            name: card.name().to_string(),
+            attack: card.attack().unwrap_or(0) as i32,
+            health,
+            max_health: health,
+            keywords: text
+                .abilities
+                .iter()
+                .filter_map(|a| match a {
+                    Ability::Static(k) => Some(*k),
+                    _ => None,
+                })
+                .collect(),
+            deathrattle: triggered(&text, Keyword::Deathrattle),
+            attacks_made: 0,
+            sleeping: true,
+            frozen: false,
+        }
+    }
+
+    /// A token such as the "2/2 Pirate" in "Summon a 2/2 Pirate".
+    fn token(description: &str, attack: i32, health: i32) -> Minion {
+        Minion {
+            card: 0,
+            name: description.to_string(),
+            attack,
+            health,
+            max_health: health,
+            keywords: Vec::new(),
+            deathrattle: Vec::new(),
+            attacks_made: 0,
+            sleeping: true,
+            frozen: false,
+        }
+    }
+
+    pub fn has(&self, keyword: Keyword) -> bool {
+        self.keywords.contains(&keyword)
+    }
+
+    fn max_attacks(&self) -> u8 {
+        if self.has(Keyword::Windfury) { 2 } else { 1 }
+    }
+
+    /// Whether the minion may attack now, and if so whether only minions are allowed targets.
+    fn attack_permission(&self) -> Option<bool> {
+        if self.attack <= 0 || self.frozen || self.attacks_made >= self.max_attacks() {
+            return None;
+        }
+        match (
+            self.sleeping,
+            self.has(Keyword::Charge),
+            self.has(Keyword::Rush),
+        ) {
+            (false, _, _) | (true, true, _) => Some(false),
+            (true, false, true) => Some(true),
+            (true, false, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weapon {
+    pub card: u32,
+    pub attack: i32,
+    pub durability: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub card: u32,
+    pub durability: i32,
+    pub effects: Vec<Effect>,
+    pub used_this_turn: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hero {
+    pub health: i32,
+    pub max_health: i32,
+    pub armor: i32,
+    pub weapon: Option<Weapon>,
+    pub attacks_made: u8,
+    pub frozen: bool,
+}
+
+impl Hero {
+    pub fn attack(&self) -> i32 {
+        self.weapon.as_ref().map_or(0, |w| w.attack)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub hero: Hero,
+    pub mana: u8,
+    pub max_mana: u8,
+    /// The top of the deck is the last card.
+    pub deck: Vec<OwnedCard>,
+    pub hand: Vec<OwnedCard>,
+    pub board: Vec<Minion>,
+    pub locations: Vec<Location>,
+    pub fatigue: i32,
+    pub cards_played_this_turn: u32,
+}
+
+impl Player {
+    fn new(deck: Vec<OwnedCard>) -> Player {
+        Player {
+            hero: Hero {
+                health: STARTING_HEALTH,
+                max_health: STARTING_HEALTH,
+                armor: 0,
+                weapon: None,
+                attacks_made: 0,
+                frozen: false,
+            },
+            mana: 0,
+            max_mana: 0,
+            deck,
+            hand: Vec::new(),
+            board: Vec::new(),
+            locations: Vec::new(),
+            fatigue: 0,
+            cards_played_this_turn: 0,
+        }
+    }
+
+    /// Minions and locations share the board's seven spaces.
+    pub fn board_full(&self) -> bool {
+        self.board.len() + self.locations.len() >= MAX_BOARD
+    }
+
+    fn has_taunt(&self) -> bool {
+        self.board
+            .iter()
+            .any(|m| m.has(Keyword::Taunt) && !m.has(Keyword::Stealth))
+    }
+}
+
+fn triggered(text: &CardText, trigger: Keyword) -> Vec<Effect> {
+    text.abilities
+        .iter()
+        .filter_map(|a| match a {
+            Ability::Triggered {
+                trigger: t,
+                effects,
+            } if *t == trigger => Some(effects.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn untriggered(text: &CardText) -> Vec<Effect> {
+    text.abilities
+        .iter()
+        .filter_map(|a| match a {
+            Ability::Effect(effect) => Some(effect.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reads the `2/2` in "a 2/2 Pirate".
+/// An amount from card text as the engine's signed numbers, capped rather than wrapping.
+fn amount(n: u32) -> i32 {
+    i32::try_from(n).unwrap_or(i32::MAX)
+}
+
+fn token_stats(description: &str) -> Option<(i32, i32)> {
+    description.split_whitespace().find_map(|word| {
+        let (attack, health) = word.split_once('/')?;
+        Some((attack.parse().ok()?, health.parse().ok()?))
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    players: [Player; 2],
+    current: usize,
+    turn: u32,
+    rng: Rng,
+    log: Vec<Event>,
+    outcome: Option<Outcome>,
+}
+
+impl Game {
+    /// Shuffles both decks, picks who goes first, deals opening hands (3 cards, or 4 and The
+    /// Coin going second) and starts the first turn.
+    pub fn new(first_deck: &[Card], second_deck: &[Card], seed: u64) -> Game {
+        let mut rng = Rng::new(seed);
+        let mut decks = [first_deck, second_deck].map(|deck| {
+            deck.iter()
+                .map(|card| card.clone().into_owned())
+                .collect::<Vec<_>>()
+        });
+        for deck in &mut decks {
+            rng.shuffle(deck);
+        }
+        let [a, b] = decks;
+        let first = rng.below(2);
+
+        let mut game = Game {
+            players: [Player::new(a), Player::new(b)],
+            current: first,
+            turn: 0,
+            rng,
+            log: vec![Event::GameStarted { first }],
+            outcome: None,
+        };
+        for _ in 0..3 {
+            game.draw(first);
+        }
+        for _ in 0..4 {
+            game.draw(1 - first);
+        }
+        game.players[1 - first].hand.push(the_coin());
+        game.start_turn();
+        game
+    }
+
+    pub fn player(&self, index: usize) -> &Player {
+        &self.players[index]
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.current
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn log(&self) -> &[Event] {
+        &self.log
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// A 64-bit FNV-1a hash of both players, the turn, the outcome and the random state, leaving
+    /// out the event log. Equal games hash equally within one build.
+    pub fn state_hash(&self) -> u64 {
+        let state = format!(
+            "{:?}",
+            (
+                &self.players,
+                self.current,
+                self.turn,
+                &self.rng,
+                self.outcome
+            )
+        );
+        state.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    /// The game as `player` sees it: both decks and the opponent's hand are replaced with
+    /// [`hidden_card`]s, the log is dropped and the random state is reseeded, so the copy can be
+    /// played forward without giving away what comes next.
+    pub fn seen_by(&self, player: usize) -> Game {
+        let mut game = self.clone();
+        for (i, p) in game.players.iter_mut().enumerate() {
+            p.deck.fill_with(hidden_card);
+            if i != player {
+                p.hand.fill_with(hidden_card);
+            }
+        }
+        game.log.clear();
+        game.rng = Rng::new(0);
+        game.rng = Rng::new(game.state_hash());
+        game
+    }
+
+    pub fn apply(&mut self, action: Action) -> Result<(), ActionError> {
+        if self.outcome.is_some() {
+            return Err(ActionError::GameOver);
+        }
+        match action {
+            Action::PlayCard { hand_index, target } => self.play_card(hand_index, target)?,
+            Action::Attack { attacker, defender } => self.attack(attacker, defender)?,
+            Action::UseLocation { index, target } => self.use_location(index, target)?,
+            Action::EndTurn => self.end_turn(),
+        }
+        self.resolve_deaths();
+        Ok(())
+    }
+
+    fn opponent(&self) -> usize {
+        1 - self.current
+    }
+
+    fn emit(&mut self, event: Event) {
+        self.log.push(event);
+    }
+
+    /// Turns a relative target into (player index, character), checking that it exists and is
+    /// not a Stealthed enemy.
+    fn resolve_target(&self, target: Target) -> Result<(usize, Character), ActionError> {
+        let player = match target.side {
+            Side::Friendly => self.current,
+            Side::Enemy => self.opponent(),
+        };
+        if let Character::Minion(i) = target.character {
+            let minion = self.players[player]
+                .board
+                .get(i)
+                .ok_or(ActionError::InvalidTarget)?;
+            if target.side == Side::Enemy && minion.has(Keyword::Stealth) {
+                return Err(ActionError::InvalidTarget);
+            }
+        }
+        Ok((player, target.character))
+    }
+
+    fn start_turn(&mut self) {
+        self.turn += 1;
+        let p = self.current;
+        let player = &mut self.players[p];
+        player.max_mana = (player.max_mana + 1).min(MAX_MANA);
+        player.mana = player.max_mana;
+        player.cards_played_this_turn = 0;
+        player.hero.attacks_made = 0;
+        for minion in &mut player.board {
+            minion.sleeping = false;
+            minion.attacks_made = 0;
+        }
+        for location in &mut player.locations {
+            location.used_this_turn = false;
+        }
+        self.emit(Event::TurnStarted {
+            player: p,
+            turn: self.turn,
+        });
+        self.draw(p);
+        self.check_game_over();
+    }
+
+    fn end_turn(&mut self) {
+        let p = self.current;
+        let player = &mut self.players[p];
+        player.hero.frozen = false;
+        for minion in &mut player.board {
+            minion.frozen = false;
+        }
+        self.emit(Event::TurnEnded { player: p });
+        if self.turn >= MAX_TURNS {
+            self.finish(Outcome::Draw);
+            return;
+        }
+        self.current = 1 - p;
+        self.start_turn();
+    }
+
+    fn draw(&mut self, p: usize) {
+        let player = &mut self.players[p];
+        match player.deck.pop() {
+            Some(card) if player.hand.len() >= MAX_HAND => {
+                let id = card.id();
+                self.emit(Event::Burned {
+                    player: p,
+                    card: id,
+                });
+            }
+            Some(card) => {
+                let id = card.id();
+                player.hand.push(card);
+                self.emit(Event::Drew {
+                    player: p,
+                    card: id,
+                });
+            }
+            None => {
+                player.fatigue = player.fatigue.saturating_add(1);
+                let damage = player.fatigue;
+                self.emit(Event::Fatigue { player: p, damage });
+                self.damage(p, Character::Hero, damage);
+            }
+        }
+    }
+
+    fn play_card(&mut self, hand_index: usize, target: Option<Target>) -> Result<(), ActionError> {
+        let p = self.current;
+        let card = self.players[p]
+            .hand
+            .get(hand_index)
+            .ok_or(ActionError::NoSuchCard(hand_index))?;
+        let cost = card.mana_cost();
+        let available = self.players[p].mana;
+        if cost > available {
+            return Err(ActionError::NotEnoughMana { cost, available });
+        }
+        let needs_space = matches!(card, Card::Minion { .. } | Card::Location { .. });
+        if needs_space && self.players[p].board_full() {
+            return Err(ActionError::BoardFull);
+        }
+        if let Some(target) = target {
+            self.resolve_target(target)?;
+        }
+
+        let card = self.players[p].hand.remove(hand_index);
+        let text = card.parsed_text();
+        let combo = self.players[p].cards_played_this_turn > 0;
+        self.players[p].mana -= cost;
+        self.players[p].cards_played_this_turn += 1;
+        self.emit(Event::Played {
+            player: p,
+            card: card.id(),
+        });
+
+        let mut effects = Vec::new();
+        let mut source = None;
+        match &card {
+            Card::Spell { .. } => effects.extend(untriggered(&text)),
+            Card::Minion { .. } => {
+                self.players[p].board.push(Minion::from_card(&card));
+                source = Some(self.players[p].board.len() - 1);
+                self.emit(Event::Summoned {
+                    player: p,
+                    card: card.id(),
+                });
+            }
+            Card::Location { durability, .. } => {
+                self.players[p].locations.push(Location {
+                    card: card.id(),
+                    durability: *durability as i32,
+                    effects: untriggered(&text),
+                    used_this_turn: false,
+                });
+            }
+            Card::Weapon {
+                attack, durability, ..
+            } => {
+                if let Some(old) = self.players[p].hero.weapon.take() {
+                    self.emit(Event::WeaponBroke {
+                        player: p,
+                        card: old.card,
+                    });
+                }
+                self.players[p].hero.weapon = Some(Weapon {
+                    card: card.id(),
+                    attack: *attack as i32,
+                    durability: *durability as i32,
+                });
+                self.emit(Event::Equipped {
+                    player: p,
+                    card: card.id(),
+                });
+            }
+            Card::Hero { armor, .. } => self.gain_armor(p, *armor as i32),
+        }
+        effects.extend(triggered(&text, Keyword::Battlecry));
+        if combo {
+            effects.extend(triggered(&text, Keyword::Combo));
+        }
+        self.run_effects(p, &effects, target, source);
+        Ok(())
+    }
+
+    fn attack(&mut self, attacker: Character, defender: Character) -> Result<(), ActionError> {
+        let p = self.current;
+        let o = self.opponent();
+
+        let attack = match attacker {
+            Character::Hero => {
+                let hero = &self.players[p].hero;
+                if hero.attack() <= 0 || hero.frozen || hero.attacks_made > 0 {
+                    return Err(ActionError::CannotAttack);
+                }
+                hero.attack()
+            }
+            Character::Minion(i) => {
+                let minion = self.players[p]
+                    .board
+                    .get(i)
+                    .ok_or(ActionError::CannotAttack)?;
+                let minions_only = minion
+                    .attack_permission()
+                    .ok_or(ActionError::CannotAttack)?;
+                if minions_only && defender == Character::Hero {
+                    return Err(ActionError::InvalidTarget);
+                }
+                minion.attack
+            }
+        };
+        self.resolve_target(Target {
+            side: Side::Enemy,
+            character: defender,
+        })?;
+        let defender_is_taunt = match defender {
+            Character::Minion(i) => self.players[o].board[i].has(Keyword::Taunt),
+            Character::Hero => false,
+        };
+        if self.players[o].has_taunt() && !defender_is_taunt {
+            return Err(ActionError::MustAttackTaunt);
+        }
+
+        self.emit(Event::Attacked {
+            player: p,
+            attacker,
+            defender,
+        });
+        let counter = match defender {
+            Character::Hero => 0,
+            Character::Minion(i) => self.players[o].board[i].attack,
+        };
+        match attacker {
+            Character::Hero => {
+                let hero = &mut self.players[p].hero;
+                hero.attacks_made += 1;
+                if let Some(weapon) = &mut hero.weapon {
+                    weapon.durability -= 1;
+                }
+            }
+            Character::Minion(i) => {
+                let minion = &mut self.players[p].board[i];
+                minion.attacks_made += 1;
+                minion.keywords.retain(|k| *k != Keyword::Stealth);
+            }
+        }
+        let hit = [
+            self.damage(o, defender, attack),
+            self.damage(p, attacker, counter),
+        ];
+        self.poison(p, attacker, o, defender, hit);
+
+        if let Some(weapon) = &self.players[p].hero.weapon
+            && weapon.durability <= 0
+        {
+            let card = weapon.card;
+            self.players[p].hero.weapon = None;
+            self.emit(Event::WeaponBroke { player: p, card });
+        }
+        Ok(())
+    }
+
+    /// Poisonous minions destroy any minion they damage in combat. `hit` says whether the
+    /// defender and the attacker took damage.
+    fn poison(
+        &mut self,
+        p: usize,
+        attacker: Character,
+        o: usize,
+        defender: Character,
+        hit: [bool; 2],
+    ) {
+        if let (Character::Minion(a), Character::Minion(d)) = (attacker, defender) {
+            if hit[0] && self.players[p].board[a].has(Keyword::Poisonous) {
+                self.players[o].board[d].health = 0;
+            }
+            if hit[1] && self.players[o].board[d].has(Keyword::Poisonous) {
+                self.players[p].board[a].health = 0;
+            }
+        }
+    }
+
+    fn use_location(&mut self, index: usize, target: Option<Target>) -> Result<(), ActionError> {
+        let p = self.current;
+        let location = self.players[p]
+            .locations
+            .get(index)
+            .ok_or(ActionError::LocationNotReady)?;
+        if location.used_this_turn {
+            return Err(ActionError::LocationNotReady);
+        }
+        if let Some(target) = target {
+            self.resolve_target(target)?;
+        }
+        let location = &mut self.players[p].locations[index];
+        location.used_this_turn = true;
+        location.durability -= 1;
+        let card = location.card;
+        let effects = location.effects.clone();
+        self.emit(Event::LocationUsed { player: p, card });
+        self.run_effects(p, &effects, target, None);
+        if self.players[p].locations[index].durability <= 0 {
+            self.players[p].locations.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Deals `amount` damage, returning whether any was dealt: nothing is for a non-positive
+    /// amount, a missing minion or one whose Divine Shield absorbs the hit.
+    fn damage(&mut self, player: usize, character: Character, amount: i32) -> bool {
+        if amount <= 0 {
+            return false;
+        }
+        match character {
+            Character::Hero => {
+                let hero = &mut self.players[player].hero;
+                let absorbed = hero.armor.min(amount);
+                hero.armor -= absorbed;
+                hero.health = hero.health.saturating_sub(amount - absorbed);
+            }
+            Character::Minion(i) => {
+                let Some(minion) = self.players[player].board.get_mut(i) else {
+                    return false;
+                };
+                if minion.has(Keyword::DivineShield) {
+                    minion.keywords.retain(|k| *k != Keyword::DivineShield);
+                    return false;
+                }
+                minion.health = minion.health.saturating_sub(amount);
+            }
+        }
+        self.emit(Event::Damaged {
+            player,
+            character,
+            amount,
+        });
+        true
+    }
+
+    fn heal(&mut self, player: usize, character: Character, amount: i32) {
+        let (health, max) = match character {
+            Character::Hero => {
+                let hero = &mut self.players[player].hero;
+                (&mut hero.health, hero.max_health)
+            }
+            Character::Minion(i) => match self.players[player].board.get_mut(i) {
+                Some(minion) => (&mut minion.health, minion.max_health),
+                None => return,
+            },
+        };
+        let healed = amount.min(max.saturating_sub(*health)).max(0);
+        *health = health.saturating_add(healed);
+        if healed > 0 {
+            self.emit(Event::Healed {
+                player,
+                character,
+                amount: healed,
+            });
+        }
+    }
+
+    fn gain_armor(&mut self, player: usize, amount: i32) {
+        if amount > 0 {
+            let hero = &mut self.players[player].hero;
+            hero.armor = hero.armor.saturating_add(amount);
+            self.emit(Event::ArmorGained { player, amount });
+        }
+    }
+
+    /// The minion an effect such as a buff lands on: the target if it is a minion, else the
+    /// card's own minion, else a random friendly minion.
+    fn minion_for(
+        &mut self,
+        p: usize,
+        target: Option<(usize, Character)>,
+        source: Option<usize>,
+    ) -> Option<(usize, usize)> {
+        match (target, source) {
+            (Some((player, Character::Minion(i))), _) => Some((player, i)),
+            (_, Some(i)) => Some((p, i)),
+            _ if self.players[p].board.is_empty() => None,
+            _ => Some((p, self.rng.below(self.players[p].board.len()))),
+        }
+    }
+
+    fn run_effects(
+        &mut self,
+        p: usize,
+        effects: &[Effect],
+        target: Option<Target>,
+        source: Option<usize>,
+    ) {
+        let target = target.and_then(|t| self.resolve_target(t).ok());
+        for effect in effects {
+            match effect {
+                Effect::Damage(n) => {
+                    let (player, character) = target.unwrap_or((1 - p, Character::Hero));
+                    self.damage(player, character, amount(*n));
+                }
+                Effect::Heal(n) => {
+                    let (player, character) = target.unwrap_or((p, Character::Hero));
+                    self.heal(player, character, amount(*n));
+                }
+                Effect::Draw(n) => {
+                    // Once fatigue has killed the hero, further draws change nothing.
+                    for _ in 0..*n {
+                        if self.players[p].hero.health <= 0 {
+                            break;
+                        }
+                        self.draw(p);
+                    }
+                }
+                Effect::Armor(n) => self.gain_armor(p, amount(*n)),
+                Effect::Mana(n) => {
+                    let player = &mut self.players[p];
+                    player.mana = player
+                        .mana
+                        .saturating_add(u8::try_from(*n).unwrap_or(u8::MAX))
+                        .min(MAX_MANA);
+                }
+                Effect::Buff { attack, health } => {
+                    if let Some((player, i)) = self.minion_for(p, target, source) {
+                        let minion = &mut self.players[player].board[i];
+                        minion.attack = minion.attack.saturating_add(*attack).max(0);
+                        minion.health = minion.health.saturating_add(*health);
+                        minion.max_health = minion.max_health.saturating_add(*health);
+                    }
+                }
+                Effect::Summon(description) => {
+                    if let Some((attack, health)) = token_stats(description)
+                        && !self.players[p].board_full()
+                    {
+                        self.players[p]
+                            .board
+                            .push(Minion::token(description, attack, health));
+                        self.emit(Event::Summoned { player: p, card: 0 });
+                    }
+                }
+                Effect::Grant(keyword) => {
+                    if let Some((player, i)) = self.minion_for(p, target, source) {
+                        let minion = &mut self.players[player].board[i];
+                        if !minion.has(*keyword) {
+                            minion.keywords.push(*keyword);
+                        }
+                    }
+                }
+                Effect::Keyword(Keyword::Freeze) => match target {
+                    Some((player, Character::Hero)) => self.players[player].hero.frozen = true,
+                    Some((player, Character::Minion(i))) => {
+                        self.players[player].board[i].frozen = true
+                    }
+                    None => {}
+                },
+                Effect::Keyword(Keyword::Silence) => {
+                    if let Some((player, Character::Minion(i))) = target {
+                        let minion = &mut self.players[player].board[i];
+                        minion.keywords.clear();
+                        minion.deathrattle.clear();
+                    }
+                }
+                Effect::Keyword(_) | Effect::Discover(_) | Effect::Other(_) => {}
+            }
+        }
+    }
+
+    /// Removes dead minions, runs their Deathrattles (which may kill more) and checks heroes.
+    fn resolve_deaths(&mut self) {
+        loop {
+            let mut dead = Vec::new();
+            for p in [self.current, 1 - self.current] {
+                let (alive, died): (Vec<Minion>, Vec<Minion>) =
+                    std::mem::take(&mut self.players[p].board)
+                        .into_iter()
+                        .partition(|m| m.health > 0);
+                self.players[p].board = alive;
+                dead.extend(died.into_iter().map(|m| (p, m)));
+            }
+            if dead.is_empty() {
+                break;
+            }
+            for (p, minion) in &dead {
+                self.emit(Event::Died {
+                    player: *p,
+                    card: minion.card,
+                });
+            }
+            for (p, minion) in dead {
+                self.run_effects(p, &minion.deathrattle, None, None);
+            }
+        }
+        self.check_game_over();
+    }
+
+    fn check_game_over(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        let dead = self.players.each_ref().map(|p| p.hero.health <= 0);
+        match dead {
+            [true, true] => self.finish(Outcome::Draw),
+            [true, false] => self.finish(Outcome::Won(1)),
+            [false, true] => self.finish(Outcome::Won(0)),
+            [false, false] => {}
+        }
+    }
+
+    fn finish(&mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+        self.emit(Event::GameOver { outcome });
+    }
+}
+
+/// Cards shared by the tests of the engine and of the modules built on it.
+#[cfg(test)]
+pub(crate) mod test_cards {
+    use super::*;
+
+    /// A neutral common minion named "Minion".
+    pub(crate) fn minion(
+        id: u32,
+        mana_cost: u8,
+        attack: u8,
+        health: u8,
+        text: &'static str,
+    ) -> OwnedCard {
+        Card::Minion {
+            id,
+            name: Cow::Borrowed("Minion"),
+            mana_cost,
+            attack,
+            health,
+            minion_types: Vec::new(),
+            classes: vec![Class::Neutral],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(text),
+        }
+    }
+
+    /// Thirty vanilla minions costing 1 to 4 mana, each with `attack` and 3 health.
+    pub(crate) fn deck(attack: u8) -> Vec<OwnedCard> {
+        (0..30)
+            .map(|i| minion(i, 1 + (i % 4) as u8, attack, 3, ""))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_cards::minion;
+    use super::*;
+
+    fn deck() -> Vec<OwnedCard> {
+        (0..30)
+            .map(|i| minion(i, 1 + (i % 3) as u8, 2, 2, ""))
+            .collect()
+    }
+
+    /// Plays the first affordable card each turn, attacks face, and ends the turn.
+    fn play_out(game: &mut Game) {
+        while game.outcome().is_none() {
+            let p = game.current_player();
+            let mana = game.player(p).mana;
+            if let Some(i) = game
+                .player(p)
+                .hand
+                .iter()
+                .position(|c| c.mana_cost() <= mana)
+            {
+                let _ = game.apply(Action::PlayCard {
+                    hand_index: i,
+                    target: None,
+                });
+            }
+            for i in 0..game.player(p).board.len() {
+                let _ = game.apply(Action::Attack {
+                    attacker: Character::Minion(i),
+                    defender: Character::Hero,
+                });
+            }
+            if game.outcome().is_none() {
+                game.apply(Action::EndTurn).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_replays_exactly() {
+        let mut a = Game::new(&deck(), &deck(), 7);
+        let mut b = Game::new(&deck(), &deck(), 7);
+        play_out(&mut a);
+        play_out(&mut b);
+        assert_eq!(a.log(), b.log());
+        assert!(a.outcome().is_some());
+    }
+
+    #[test]
+    fn opening_hands_mana_and_coin() {
+        let game = Game::new(&deck(), &deck(), 1);
+        let first = game.current_player();
+        assert_eq!(game.player(first).hand.len(), 4);
+        assert_eq!(game.player(1 - first).hand.len(), 5);
+        assert_eq!(game.player(1 - first).hand[4].id(), COIN_ID);
+        assert_eq!(game.player(first).mana, 1);
+        assert_eq!(game.turn(), 1);
+    }
+
+    #[test]
+    fn taunt_battlecry_and_fatigue() {
+        let mut game = Game::new(&[], &[], 3);
+        let p = game.current_player();
+        game.players[p].hand = vec![minion(1, 0, 1, 1, "Battlecry: Deal 2 damage.")];
+        game.players[1 - p].board = vec![
+            Minion::from_card(&minion(2, 0, 0, 5, "Taunt")),
+            Minion::from_card(&minion(3, 0, 0, 1, "")),
+        ];
+
+        game.apply(Action::PlayCard {
+            hand_index: 0,
+            target: Some(Target {
+                side: Side::Enemy,
+                character: Character::Minion(1),
+            }),
+        })
+        .unwrap();
+        assert_eq!(game.player(1 - p).board.len(), 1);
+        assert!(game.log().contains(&Event::Died {
+            player: 1 - p,
+            card: 3
+        }));
+
+        game.players[p].board[0].sleeping = false;
+        assert_eq!(
+            game.apply(Action::Attack {
+                attacker: Character::Minion(0),
+                defender: Character::Hero,
+            }),
+            Err(ActionError::MustAttackTaunt)
+        );
+
+        // Both decks are empty, so the three opening draws and the first turn's draw were fatigue.
+        assert!(game.log().contains(&Event::Fatigue {
+            player: p,
+            damage: 1
+        }));
+        assert_eq!(
+            game.player(p).hero.health,
+            STARTING_HEALTH - (1 + 2 + 3 + 4)
+        );
+    }
+
+    #[test]
+    fn mana_gain_is_capped() {
+        let mut game = Game::new(&[], &[], 2);
+        let p = game.current_player();
+        let mut spell = the_coin();
+        if let Card::Spell { text, .. } = &mut spell {
+            *text = Cow::Borrowed("Gain 255 Mana Crystals this turn only.");
+        }
+        game.players[p].hand = vec![spell];
+        game.apply(Action::PlayCard {
+            hand_index: 0,
+            target: None,
+        })
+        .unwrap();
+        assert_eq!(game.player(p).mana, MAX_MANA);
+    }
+
+    #[test]
+    fn large_amounts_saturate() {
+        let spell = |text: &'static str| {
+            let mut spell = the_coin();
+            if let Card::Spell { text: t, .. } = &mut spell {
+                *t = Cow::Borrowed(text);
+            }
+            spell
+        };
+        let play = |game: &mut Game| {
+            game.apply(Action::PlayCard {
+                hand_index: 0,
+                target: None,
+            })
+        };
+
+        let mut game = Game::new(&[], &[], 5);
+        let p = game.current_player();
+        game.players[p].hand = vec![
+            spell("Gain 2147483647 Armor."),
+            spell("Gain 2147483647 Armor."),
+            spell("Deal 4294967295 damage."),
+        ];
+        play(&mut game).unwrap();
+        play(&mut game).unwrap();
+        assert_eq!(game.player(p).hero.armor, i32::MAX);
+        play(&mut game).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Won(p)));
+
+        let mut game = Game::new(&[], &[], 5);
+        let p = game.current_player();
+        game.players[p].hand = vec![spell("Draw 100000 cards.")];
+        play(&mut game).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Won(1 - p)));
+        assert!(game.log().len() < 100);
+    }
+
+    #[test]
+    fn poisonous_needs_to_deal_damage() {
+        let mut game = Game::new(&[], &[], 4);
+        let p = game.current_player();
+        game.players[p].board = vec![
+            Minion::from_card(&minion(1, 0, 1, 3, "Poisonous")),
+            Minion::from_card(&minion(2, 0, 1, 3, "")),
+        ];
+        game.players[1 - p].board = vec![
+            Minion::from_card(&minion(3, 0, 1, 3, "Divine Shield")),
+            Minion::from_card(&minion(4, 0, 0, 3, "Poisonous")),
+        ];
+        for minion in &mut game.players[p].board {
+            minion.sleeping = false;
+        }
+
+        for (attacker, defender) in [(0, 0), (1, 1)] {
+            game.apply(Action::Attack {
+                attacker: Character::Minion(attacker),
+                defender: Character::Minion(defender),
+            })
+            .unwrap();
+        }
+        let shielded = &game.player(1 - p).board[0];
+        assert!(!shielded.has(Keyword::DivineShield));
+        assert_eq!(shielded.health, 3);
+        assert_eq!(game.player(p).board[1].health, 3);
+    }
+}
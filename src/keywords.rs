@@ -0,0 +1,501 @@
+//! Parses card text into keywords and effects.
+//!
+//! A sentence that starts with a trigger label such as `Battlecry:` opens a triggered ability,
+//! and the sentences after it belong to that ability until the next label. A sentence made only
+//! of keyword names (`Taunt, Divine Shield`) gives the card those keywords. Anything else is an
+//! effect, so "Your Battlecries trigger twice" mentions Battlecry without the card having it.
+
+use std::fmt;
+
+use crate::{Card, FlavorText};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Keyword {
+    // Labels that open a triggered ability.
+    Battlecry,
+    Deathrattle,
+    Combo,
+    Outcast,
+    Inspire,
+    Frenzy,
+    Overkill,
+    Spellburst,
+    HonorableKill,
+    Infuse,
+    Corrupt,
+    Manathirst,
+    Finale,
+    Quickdraw,
+    Forge,
+    Secret,
+    ChooseOne,
+    // Keywords a card simply has.
+    Taunt,
+    Rush,
+    Charge,
+    DivineShield,
+    Windfury,
+    Stealth,
+    Lifesteal,
+    Poisonous,
+    Reborn,
+    Elusive,
+    Magnetic,
+    Echo,
+    Tradeable,
+    Overload,
+    SpellDamage,
+    // Keywords that name an action.
+    Discover,
+    Freeze,
+    Silence,
+    Adapt,
+    Recruit,
+    Dredge,
+    Excavate,
+    Herald,
+}
+
+const KEYWORDS: [(&str, Keyword); 40] = [
+    ("battlecry", Keyword::Battlecry),
+    ("deathrattle", Keyword::Deathrattle),
+    ("combo", Keyword::Combo),
+    ("outcast", Keyword::Outcast),
+    ("inspire", Keyword::Inspire),
+    ("frenzy", Keyword::Frenzy),
+    ("overkill", Keyword::Overkill),
+    ("spellburst", Keyword::Spellburst),
+    ("honorable kill", Keyword::HonorableKill),
+    ("infuse", Keyword::Infuse),
+    ("corrupt", Keyword::Corrupt),
+    ("manathirst", Keyword::Manathirst),
+    ("finale", Keyword::Finale),
+    ("quickdraw", Keyword::Quickdraw),
+    ("forge", Keyword::Forge),
+    ("secret", Keyword::Secret),
+    ("choose one", Keyword::ChooseOne),
+    ("taunt", Keyword::Taunt),
+    ("rush", Keyword::Rush),
+    ("charge", Keyword::Charge),
+    ("divine shield", Keyword::DivineShield),
+    ("windfury", Keyword::Windfury),
+    ("stealth", Keyword::Stealth),
+    ("lifesteal", Keyword::Lifesteal),
+    ("poisonous", Keyword::Poisonous),
+    ("reborn", Keyword::Reborn),
+    ("elusive", Keyword::Elusive),
+    ("magnetic", Keyword::Magnetic),
+    ("echo", Keyword::Echo),
+    ("tradeable", Keyword::Tradeable),
+    ("overload", Keyword::Overload),
+    ("spell damage", Keyword::SpellDamage),
+    ("discover", Keyword::Discover),
+    ("freeze", Keyword::Freeze),
+    ("silence", Keyword::Silence),
+    ("adapt", Keyword::Adapt),
+    ("recruit", Keyword::Recruit),
+    ("dredge", Keyword::Dredge),
+    ("excavate", Keyword::Excavate),
+    ("herald", Keyword::Herald),
+];
+
+impl Keyword {
+    pub fn all() -> impl Iterator<Item = Keyword> {
+        KEYWORDS.iter().map(|&(_, keyword)| keyword)
+    }
+
+    /// The keyword as it is written on cards, in lowercase.
+    pub fn name(self) -> &'static str {
+        KEYWORDS
+            .iter()
+            .find(|&&(_, k)| k == self)
+            .map(|&(name, _)| name)
+            .unwrap_or_default()
+    }
+
+    /// Looks a keyword up by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Keyword> {
+        let name = name.trim().to_lowercase();
+        KEYWORDS.iter().find(|&&(n, _)| n == name).map(|&(_, k)| k)
+    }
+
+    pub fn is_trigger(self) -> bool {
+        (self as u8) <= Keyword::ChooseOne as u8
+    }
+
+    pub fn is_static(self) -> bool {
+        (Keyword::Taunt as u8..=Keyword::SpellDamage as u8).contains(&(self as u8))
+    }
+}
+
+/// Writes the keyword the way cards print it, e.g. "Divine Shield".
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words: Vec<String> = self
+            .name()
+            .split(' ')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect();
+        f.pad(&words.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    Damage(u32),
+    Heal(u32),
+    Draw(u32),
+    Armor(u32),
+    /// Mana Crystals gained for this turn only, as on The Coin.
+    Mana(u32),
+    Buff {
+        attack: i32,
+        health: i32,
+    },
+    /// What to summon, e.g. "a 1-Cost minion".
+    Summon(String),
+    /// What to discover, e.g. "a Pirate".
+    Discover(String),
+    /// Gives another character a keyword, as in "Give a minion Taunt".
+    Grant(Keyword),
+    /// A keyword that is itself the action, such as Freeze or Silence.
+    Keyword(Keyword),
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ability {
+    Static(Keyword),
+    Triggered {
+        trigger: Keyword,
+        effects: Vec<Effect>,
+    },
+    Effect(Effect),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardText {
+    pub abilities: Vec<Ability>,
+}
+
+impl CardText {
+    pub fn parse(text: &str) -> CardText {
+        let mut abilities = Vec::new();
+        for sentence in sentences(&strip_markup(text)) {
+            if let Some((trigger, rest)) = trigger_label(&sentence) {
+                if trigger == Keyword::Overload {
+                    abilities.push(Ability::Static(trigger));
+                    continue;
+                }
+                abilities.push(Ability::Triggered {
+                    trigger,
+                    effects: parse_effects(rest),
+                });
+            } else if let Some(keywords) = keyword_list(&sentence) {
+                abilities.extend(keywords.into_iter().map(Ability::Static));
+            } else if let Some(Ability::Triggered { effects, .. }) = abilities.last_mut() {
+                effects.extend(parse_effects(&sentence));
+            } else {
+                abilities.extend(parse_effects(&sentence).into_iter().map(Ability::Effect));
+            }
+        }
+        CardText { abilities }
+    }
+
+    /// Every effect, whether triggered or not.
+    pub fn effects(&self) -> impl Iterator<Item = &Effect> {
+        self.abilities.iter().flat_map(|ability| match ability {
+            Ability::Static(_) => [].iter(),
+            Ability::Triggered { effects, .. } => effects.iter(),
+            Ability::Effect(effect) => std::slice::from_ref(effect).iter(),
+        })
+    }
+
+    /// The keywords the card itself has. Keywords it only grants to others are left out.
+    pub fn keywords(&self) -> Vec<Keyword> {
+        let mut keywords = Vec::new();
+        for ability in &self.abilities {
+            match ability {
+                Ability::Static(keyword)
+                | Ability::Triggered {
+                    trigger: keyword, ..
+                } => keywords.push(*keyword),
+                Ability::Effect(_) => {}
+            }
+        }
+        for effect in self.effects() {
+            match effect {
+                Effect::Discover(_) => keywords.push(Keyword::Discover),
+                Effect::Keyword(keyword) => keywords.push(*keyword),
+                _ => {}
+            }
+        }
+        let mut seen = Vec::new();
+        keywords.retain(|k| {
+            let new = !seen.contains(k);
+            seen.push(*k);
+            new
+        });
+        keywords
+    }
+
+    pub fn has_keyword(&self, keyword: Keyword) -> bool {
+        self.keywords().contains(&keyword)
+    }
+}
+
+impl<'a> Card<'a> {
+    pub fn parsed_text(&self) -> CardText {
+        CardText::parse(self.text())
+    }
+}
+
+/// Like [`filter_cards`](crate::filter_cards), but matches cards that have `keyword` rather than
+/// cards whose text contains its name.
+pub fn filter_keyword<'a, 'b>(deck: &'a [Card<'b>], keyword: Keyword) -> Vec<&'a Card<'b>> {
+    deck.iter()
+        .filter(|card| card.parsed_text().has_keyword(keyword))
+        .collect()
+}
+
+/// Removes `<b>`-style tags and `[x]` layout hints.
+fn strip_markup(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.replace("[x]", "").chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            '\n' if !in_tag => out.push(' '),
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Splits on `.` and `!` outside parentheses, dropping the terminators.
+fn sentences(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 && (c == '.' || c == '!') {
+            out.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Matches a leading `Battlecry:` or `Manathirst (7):` label.
+fn trigger_label(sentence: &str) -> Option<(Keyword, &str)> {
+    let (label, rest) = sentence.split_once(':')?;
+    let label = match label.find('(') {
+        Some(paren) => &label[..paren],
+        None => label,
+    };
+    let keyword = Keyword::from_name(label)?;
+    (keyword.is_trigger() || keyword == Keyword::Overload).then_some((keyword, rest.trim()))
+}
+
+/// Recognises sentences such as "Taunt, Divine Shield" or "Spell Damage +1".
+fn keyword_list(sentence: &str) -> Option<Vec<Keyword>> {
+    let lower = sentence.to_lowercase();
+    let mut rest = lower.as_str();
+    let mut keywords = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([',', ' ']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+        if rest.is_empty() {
+            break;
+        }
+        let (name, keyword) =
+            KEYWORDS
+                .iter()
+                .filter(|(_, k)| k.is_static())
+                .find(|(name, _)| {
+                    rest.strip_prefix(name)
+                        .is_some_and(|after| after.is_empty() || after.starts_with([',', ' ']))
+                })?;
+        rest = rest[name.len()..].trim_start();
+        if let Some(amount) = rest.strip_prefix('+') {
+            rest = amount.trim_start_matches(|c: char| c.is_ascii_digit());
+        }
+        keywords.push(*keyword);
+    }
+    (!keywords.is_empty()).then_some(keywords)
+}
+
+const VERBS: [&str; 14] = [
+    "deal", "restore", "draw", "gain", "give", "summon", "discover", "freeze", "silence", "adapt",
+    "recruit", "dredge", "excavate", "herald",
+];
+
+/// Splits "Gain 1 Armor and draw a card" into its two clauses.
+fn parse_effects(sentence: &str) -> Vec<Effect> {
+    let mut clauses: Vec<String> = Vec::new();
+    for part in sentence.split(" and ") {
+        let starts_with_verb = part
+            .split_whitespace()
+            .next()
+            .is_some_and(|w| VERBS.contains(&w.to_lowercase().as_str()));
+        match clauses.last_mut() {
+            Some(last) if !starts_with_verb => {
+                last.push_str(" and ");
+                last.push_str(part);
+            }
+            _ => clauses.push(part.to_string()),
+        }
+    }
+    clauses
+        .iter()
+        .map(|c| parse_effect(c.trim().trim_start_matches(',').trim()))
+        .collect()
+}
+
+/// Reads `3`, `$3`, `#3`, `a` or `an` as a number.
+fn number(word: &str) -> Option<u32> {
+    match word.to_lowercase().as_str() {
+        "a" | "an" => Some(1),
+        w => w.trim_start_matches(['$', '#']).parse().ok(),
+    }
+}
+
+/// Reads `+2/+2` or `-1/+3` as attack and health changes.
+fn stats(word: &str) -> Option<(i32, i32)> {
+    let (attack, health) = word.trim_end_matches(',').split_once('/')?;
+    Some((attack.parse().ok()?, health.parse().ok()?))
+}
+
+fn parse_effect(clause: &str) -> Effect {
+    let words: Vec<&str> = clause.split_whitespace().collect();
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    let rest = |from: usize| words.get(from..).unwrap_or_default().join(" ");
+    let other = || Effect::Other(clause.to_string());
+
+    match lower.first().map(String::as_str) {
+        Some("deal") => match (words.get(1).and_then(|w| number(w)), lower.get(2)) {
+            (Some(n), Some(unit)) if unit.starts_with("damage") => Effect::Damage(n),
+            _ => other(),
+        },
+        Some("restore") => match words.get(1).and_then(|w| number(w)) {
+            Some(n) => Effect::Heal(n),
+            None => other(),
+        },
+        Some("draw") => match (words.get(1).and_then(|w| number(w)), lower.get(2)) {
+            (Some(n), Some(unit)) if unit.starts_with("card") => Effect::Draw(n),
+            _ => other(),
+        },
+        Some("gain") => {
+            if let Some((attack, health)) = words.get(1).and_then(|w| stats(w)) {
+                Effect::Buff { attack, health }
+            } else if let (Some(n), Some("armor")) = (
+                words.get(1).and_then(|w| number(w)),
+                lower.get(2).map(String::as_str),
+            ) {
+                Effect::Armor(n)
+            } else if let (Some(n), Some("mana")) = (
+                words.get(1).and_then(|w| number(w)),
+                lower.get(2).map(String::as_str),
+            ) {
+                Effect::Mana(n)
+            } else {
+                other()
+            }
+        }
+        Some("give") => {
+            if let Some((attack, health)) = words.iter().find_map(|w| stats(w)) {
+                Effect::Buff { attack, health }
+            } else {
+                let text = lower.join(" ");
+                KEYWORDS
+                    .iter()
+                    .filter(|(_, k)| k.is_static())
+                    .find(|(name, _)| text.ends_with(name))
+                    .map_or_else(other, |&(_, k)| Effect::Grant(k))
+            }
+        }
+        Some("summon") => Effect::Summon(rest(1)),
+        Some("discover") => Effect::Discover(rest(1)),
+        Some(verb) => match Keyword::from_name(verb) {
+            Some(keyword) if !keyword.is_trigger() && !keyword.is_static() => {
+                Effect::Keyword(keyword)
+            }
+            _ => other(),
+        },
+        None => other(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_triggers_statics_and_payloads() {
+        let text = CardText::parse(
+            "<b>Taunt</b>, <b>Divine Shield</b>. <b>Battlecry:</b> Deal $3 damage and draw a card. \
+             <b>Deathrattle:</b> Summon a 2/2 Pirate.",
+        );
+        assert_eq!(
+            text.abilities,
+            vec![
+                Ability::Static(Keyword::Taunt),
+                Ability::Static(Keyword::DivineShield),
+                Ability::Triggered {
+                    trigger: Keyword::Battlecry,
+                    effects: vec![Effect::Damage(3), Effect::Draw(1)],
+                },
+                Ability::Triggered {
+                    trigger: Keyword::Deathrattle,
+                    effects: vec![Effect::Summon("a 2/2 Pirate".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mentions_are_not_keywords() {
+        let text = CardText::parse("Your Battlecries trigger twice. Give a minion Taunt.");
+        assert!(!text.has_keyword(Keyword::Battlecry));
+        assert!(!text.has_keyword(Keyword::Taunt));
+        assert!(text.effects().any(|e| *e == Effect::Grant(Keyword::Taunt)));
+
+        let combo = CardText::parse("Combo: Gain +2/+2 for each other card you've played.");
+        assert_eq!(combo.keywords(), vec![Keyword::Combo]);
+        assert_eq!(
+            combo.effects().next(),
+            Some(&Effect::Buff {
+                attack: 2,
+                health: 2
+            })
+        );
+
+        let location = CardText::parse("Summon a 1-Cost minion. Gain 1 Armor. Draw 1 card.");
+        assert_eq!(
+            location.effects().cloned().collect::<Vec<_>>(),
+            vec![
+                Effect::Summon("a 1-Cost minion".to_string()),
+                Effect::Armor(1),
+                Effect::Draw(1),
+            ]
+        );
+    }
+}
@@ -1,44 +1,141 @@
+use std::borrow::Cow;
+
+pub mod archetype;
+pub mod batch;
+pub mod bot;
+pub mod card_db;
+pub mod collection;
+pub mod deck;
+pub mod deckstring;
+pub mod diff;
+pub mod draft;
+pub mod game;
+pub mod json;
+pub mod keywords;
+pub mod locale;
+pub mod lookup;
+pub mod markup;
+pub mod names;
+pub mod odds;
+pub mod patch;
+pub mod query;
+pub mod replay;
+pub mod rng;
+pub mod script;
+pub mod stats;
+pub mod text_index;
+pub mod validate;
+
+pub use archetype::{Archetype, Similarity, cluster};
+pub use batch::{MatchJob, Pool, Results, play_matchups, run_matchups};
+pub use bot::{GreedyBot, LookaheadBot, MatchReport, Player, RandomBot, View, run_match};
+pub use card_db::CardDb;
+pub use collection::{Collection, Completion, CraftingPlan, Finish};
+pub use deck::{Deck, Format};
+pub use diff::{DeckDiff, diff_cards};
+pub use draft::{CurveScorer, Draft, PickStrategy};
+pub use game::Game;
+pub use keywords::{CardText, Keyword};
+pub use locale::{Locale, Localized, Translations};
+pub use lookup::{NameIndex, NameMatch};
+pub use markup::{Renderer, Style};
+pub use names::ParseNameError;
+pub use odds::{Mulligan, Play, Scenario, draw_probability, simulate_draws};
+pub use patch::{Changelog, Patch};
+pub use query::{QueryError, search};
+pub use replay::{Recorder, Replay, ReplayError, Viewer};
+pub use script::{Board, Interpreter, Script, ScriptError};
+pub use stats::DeckStats;
+pub use text_index::TextIndex;
+pub use validate::Violation;
+
 pub trait FlavorText {
     fn text(&self) -> &str;
 }
 
-#[derive(Debug)]
+/// The fields every card has, without matching on its variant.
+pub trait CardInfo: FlavorText {
+    fn name(&self) -> &str;
+    fn mana_cost(&self) -> u8;
+    /// The first of the card's classes; use `Card::classes` for multi-class cards.
+    fn class(&self) -> Class;
+    fn kind(&self) -> CardKind;
+    /// The first of a minion's tribes, if it has any.
+    fn minion_type(&self) -> Option<MinionType>;
+    fn spell_school(&self) -> Option<SpellSchool>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardKind {
+    Spell,
+    Minion,
+    Location,
+    Weapon,
+    Hero,
+}
+
+/// A card whose `name` and `text` either borrow from static data or own strings read at runtime.
+///
+/// Most cards belong to a single class, but dual- and tri-class cards list every class they can
+/// be played in. Minions may have any number of tribes, including none.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Card<'a> {
     Spell {
-        name: &'a str,
+        id: u32,
+        name: Cow<'a, str>,
         mana_cost: u8,
         spell_school: SpellSchool,
-        class: Class,
-        text: &'a str,
+        classes: Vec<Class>,
+        rarity: Rarity,
+        set: CardSet,
+        text: Cow<'a, str>,
     },
     Minion {
-        name: &'a str,
+        id: u32,
+        name: Cow<'a, str>,
         mana_cost: u8,
-        minion_type: MinionType,
-        class: Class,
-        text: &'a str,
+        attack: u8,
+        health: u8,
+        minion_types: Vec<MinionType>,
+        classes: Vec<Class>,
+        rarity: Rarity,
+        set: CardSet,
+        text: Cow<'a, str>,
     },
     Location {
-        name: &'a str,
+        id: u32,
+        name: Cow<'a, str>,
         mana_cost: u8,
-        class: Class,
-        text: &'a str,
+        durability: u8,
+        classes: Vec<Class>,
+        rarity: Rarity,
+        set: CardSet,
+        text: Cow<'a, str>,
     },
     Weapon {
-        name: &'a str,
+        id: u32,
+        name: Cow<'a, str>,
         mana_cost: u8,
-        class: Class,
-        text: &'a str,
+        attack: u8,
+        durability: u8,
+        classes: Vec<Class>,
+        rarity: Rarity,
+        set: CardSet,
+        text: Cow<'a, str>,
     },
     Hero {
-        name: &'a str,
+        id: u32,
+        name: Cow<'a, str>,
         mana_cost: u8,
-        class: Class,
-        text: &'a str,
+        armor: u8,
+        classes: Vec<Class>,
+        rarity: Rarity,
+        set: CardSet,
+        text: Cow<'a, str>,
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Class {
     DeathKnight,
     DemonHunter,
@@ -54,7 +151,60 @@ pub enum Class {
     Neutral,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rarity {
+    Free,
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+/// The expansion or set a card was printed in. `Classic` is the frozen set played in the Classic
+/// format, while `Legacy` holds the old Basic and Classic cards playable in Wild.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardSet {
+    Legacy,
+    Core,
+    Classic,
+    Naxxramas,
+    GoblinsVsGnomes,
+    BlackrockMountain,
+    TheGrandTournament,
+    LeagueOfExplorers,
+    WhispersOfTheOldGods,
+    OneNightInKarazhan,
+    MeanStreetsOfGadgetzan,
+    JourneyToUnGoro,
+    KnightsOfTheFrozenThrone,
+    KoboldsAndCatacombs,
+    TheWitchwood,
+    TheBoomsdayProject,
+    RastakhansRumble,
+    RiseOfShadows,
+    SaviorsOfUldum,
+    DescentOfDragons,
+    AshesOfOutland,
+    ScholomanceAcademy,
+    MadnessAtTheDarkmoonFaire,
+    ForgedInTheBarrens,
+    UnitedInStormwind,
+    FracturedInAlteracValley,
+    VoyageToTheSunkenCity,
+    MurderAtCastleNathria,
+    MarchOfTheLichKing,
+    FestivalOfLegends,
+    Titans,
+    ShowdownInTheBadlands,
+    WhizbangsWorkshop,
+    PerilsInParadise,
+    TheGreatDarkBeyond,
+    IntoTheEmeraldDream,
+    TheLostCityOfUnGoro,
+    AcrossTheTimeways,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MinionType {
     Beast,
     Demon,
@@ -70,7 +220,7 @@ pub enum MinionType {
     Undead,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SpellSchool {
     Arcane,
     Fel,
@@ -82,6 +232,307 @@ pub enum SpellSchool {
     General,
 }
 
+/// A card that owns all of its strings, e.g. one loaded from a file.
+pub type OwnedCard = Card<'static>;
+
+impl<'a> Card<'a> {
+    /// The card's DBF id, the number deckstrings and card databases use to refer to it.
+    pub fn id(&self) -> u32 {
+        match self {
+            Card::Spell { id, .. }
+            | Card::Minion { id, .. }
+            | Card::Location { id, .. }
+            | Card::Weapon { id, .. }
+            | Card::Hero { id, .. } => *id,
+        }
+    }
+
+    pub fn classes(&self) -> &[Class] {
+        match self {
+            Card::Spell { classes, .. }
+            | Card::Minion { classes, .. }
+            | Card::Location { classes, .. }
+            | Card::Weapon { classes, .. }
+            | Card::Hero { classes, .. } => classes,
+        }
+    }
+
+    /// The card's tribes. Empty for minions without one and for every other kind of card.
+    pub fn minion_types(&self) -> &[MinionType] {
+        match self {
+            Card::Minion { minion_types, .. } => minion_types,
+            _ => &[],
+        }
+    }
+
+    pub fn rarity(&self) -> Rarity {
+        match self {
+            Card::Spell { rarity, .. }
+            | Card::Minion { rarity, .. }
+            | Card::Location { rarity, .. }
+            | Card::Weapon { rarity, .. }
+            | Card::Hero { rarity, .. } => *rarity,
+        }
+    }
+
+    pub fn set(&self) -> CardSet {
+        match self {
+            Card::Spell { set, .. }
+            | Card::Minion { set, .. }
+            | Card::Location { set, .. }
+            | Card::Weapon { set, .. }
+            | Card::Hero { set, .. } => *set,
+        }
+    }
+
+    /// Attack of a minion or weapon.
+    pub fn attack(&self) -> Option<u8> {
+        match self {
+            Card::Minion { attack, .. } | Card::Weapon { attack, .. } => Some(*attack),
+            _ => None,
+        }
+    }
+
+    pub fn health(&self) -> Option<u8> {
+        match self {
+            Card::Minion { health, .. } => Some(*health),
+            _ => None,
+        }
+    }
+
+    /// Durability of a weapon or location.
+    pub fn durability(&self) -> Option<u8> {
+        match self {
+            Card::Weapon { durability, .. } | Card::Location { durability, .. } => {
+                Some(*durability)
+            }
+            _ => None,
+        }
+    }
+
+    /// Armor a hero card grants when played.
+    pub fn armor(&self) -> Option<u8> {
+        match self {
+            Card::Hero { armor, .. } => Some(*armor),
+            _ => None,
+        }
+    }
+
+    /// Copies any borrowed strings so the card no longer depends on `'a`.
+    pub fn into_owned(self) -> OwnedCard {
+        match self {
+            Card::Spell {
+                id,
+                name,
+                mana_cost,
+                spell_school,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Spell {
+                id,
+                name: Cow::Owned(name.into_owned()),
+                mana_cost,
+                spell_school,
+                classes,
+                rarity,
+                set,
+                text: Cow::Owned(text.into_owned()),
+            },
+            Card::Minion {
+                id,
+                name,
+                mana_cost,
+                attack,
+                health,
+                minion_types,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Minion {
+                id,
+                name: Cow::Owned(name.into_owned()),
+                mana_cost,
+                attack,
+                health,
+                minion_types,
+                classes,
+                rarity,
+                set,
+                text: Cow::Owned(text.into_owned()),
+            },
+            Card::Location {
+                id,
+                name,
+                mana_cost,
+                durability,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Location {
+                id,
+                name: Cow::Owned(name.into_owned()),
+                mana_cost,
+                durability,
+                classes,
+                rarity,
+                set,
+                text: Cow::Owned(text.into_owned()),
+            },
+            Card::Weapon {
+                id,
+                name,
+                mana_cost,
+                attack,
+                durability,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Weapon {
+                id,
+                name: Cow::Owned(name.into_owned()),
+                mana_cost,
+                attack,
+                durability,
+                classes,
+                rarity,
+                set,
+                text: Cow::Owned(text.into_owned()),
+            },
+            Card::Hero {
+                id,
+                name,
+                mana_cost,
+                armor,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Hero {
+                id,
+                name: Cow::Owned(name.into_owned()),
+                mana_cost,
+                armor,
+                classes,
+                rarity,
+                set,
+                text: Cow::Owned(text.into_owned()),
+            },
+        }
+    }
+
+    /// Returns a card that borrows its strings from `self`, whichever form `self` is in.
+    pub fn borrowed(&self) -> Card<'_> {
+        match self {
+            Card::Spell {
+                id,
+                name,
+                mana_cost,
+                spell_school,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Spell {
+                id: *id,
+                name: Cow::Borrowed(name),
+                mana_cost: *mana_cost,
+                spell_school: *spell_school,
+                classes: classes.clone(),
+                rarity: *rarity,
+                set: *set,
+                text: Cow::Borrowed(text),
+            },
+            Card::Minion {
+                id,
+                name,
+                mana_cost,
+                attack,
+                health,
+                minion_types,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Minion {
+                id: *id,
+                name: Cow::Borrowed(name),
+                mana_cost: *mana_cost,
+                attack: *attack,
+                health: *health,
+                minion_types: minion_types.clone(),
+                classes: classes.clone(),
+                rarity: *rarity,
+                set: *set,
+                text: Cow::Borrowed(text),
+            },
+            Card::Location {
+                id,
+                name,
+                mana_cost,
+                durability,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Location {
+                id: *id,
+                name: Cow::Borrowed(name),
+                mana_cost: *mana_cost,
+                durability: *durability,
+                classes: classes.clone(),
+                rarity: *rarity,
+                set: *set,
+                text: Cow::Borrowed(text),
+            },
+            Card::Weapon {
+                id,
+                name,
+                mana_cost,
+                attack,
+                durability,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Weapon {
+                id: *id,
+                name: Cow::Borrowed(name),
+                mana_cost: *mana_cost,
+                attack: *attack,
+                durability: *durability,
+                classes: classes.clone(),
+                rarity: *rarity,
+                set: *set,
+                text: Cow::Borrowed(text),
+            },
+            Card::Hero {
+                id,
+                name,
+                mana_cost,
+                armor,
+                classes,
+                rarity,
+                set,
+                text,
+            } => Card::Hero {
+                id: *id,
+                name: Cow::Borrowed(name),
+                mana_cost: *mana_cost,
+                armor: *armor,
+                classes: classes.clone(),
+                rarity: *rarity,
+                set: *set,
+                text: Cow::Borrowed(text),
+            },
+        }
+    }
+}
+
 impl<'a> FlavorText for Card<'a> {
     fn text(&self) -> &str {
         match self {
@@ -94,56 +545,55 @@ impl<'a> FlavorText for Card<'a> {
     }
 }
 
-fn printc(card: &Card) {
-    match card {
-        Card::Spell {
-            name,
-            mana_cost,
-            spell_school,
-            class,
-            text,
-        } => {
-            println!(
-                "Name: {} | Mana: {} | Spell school: {:?} | Class: {:?} | Text: {}",
-                name, mana_cost, spell_school, class, text
-            );
-        }
-        Card::Minion {
-            name,
-            mana_cost,
-            minion_type,
-            class,
-            text,
-        } => {
-            println!(
-                "Name: {} | Mana: {} | Minion type: {:?} | Class: {:?} | Text: {}",
-                name, mana_cost, minion_type, class, text
-            );
-        }
-        Card::Location {
-            name,
-            mana_cost,
-            class,
-            text,
-        }
-        | Card::Weapon {
-            name,
-            mana_cost,
-            class,
-            text,
-        }
-        | Card::Hero {
-            name,
-            mana_cost,
-            class,
-            text,
-        } => {
-            println!(
-                "Name: {} | Mana: {} | Class: {:?} | Text: {}",
-                name, mana_cost, class, text
-            );
+impl<'a> CardInfo for Card<'a> {
+    fn name(&self) -> &str {
+        match self {
+            Card::Spell { name, .. }
+            | Card::Minion { name, .. }
+            | Card::Location { name, .. }
+            | Card::Hero { name, .. }
+            | Card::Weapon { name, .. } => name,
+        }
+    }
+
+    fn mana_cost(&self) -> u8 {
+        match self {
+            Card::Spell { mana_cost, .. }
+            | Card::Minion { mana_cost, .. }
+            | Card::Location { mana_cost, .. }
+            | Card::Hero { mana_cost, .. }
+            | Card::Weapon { mana_cost, .. } => *mana_cost,
+        }
+    }
+
+    fn class(&self) -> Class {
+        self.classes().first().copied().unwrap_or(Class::Neutral)
+    }
+
+    fn kind(&self) -> CardKind {
+        match self {
+            Card::Spell { .. } => CardKind::Spell,
+            Card::Minion { .. } => CardKind::Minion,
+            Card::Location { .. } => CardKind::Location,
+            Card::Weapon { .. } => CardKind::Weapon,
+            Card::Hero { .. } => CardKind::Hero,
         }
     }
+
+    fn minion_type(&self) -> Option<MinionType> {
+        self.minion_types().first().copied()
+    }
+
+    fn spell_school(&self) -> Option<SpellSchool> {
+        match self {
+            Card::Spell { spell_school, .. } => Some(*spell_school),
+            _ => None,
+        }
+    }
+}
+
+fn printc(card: &Card) {
+    println!("{card}");
 }
 
 pub fn printd(deck: &[Card]) {
@@ -152,6 +602,49 @@ pub fn printd(deck: &[Card]) {
     }
 }
 
-pub fn filter_cards<'a>(deck: &'a [Card], text: &str) -> Vec<&'a Card<'a>> {
-    deck.iter().filter(|d| d.text().contains(text)).collect()
+/// Prints the cards added (`+`), removed (`-`) and changed in count (`~`) between two decks.
+pub fn printdiff(old: &[Card], new: &[Card]) {
+    print!("{}", diff_cards(old, new));
+}
+
+/// The predicate [`filter_cards`] uses: the card's text contains `text`.
+pub fn text_contains(text: &str) -> impl Fn(&Card) -> bool + '_ {
+    move |card| card.text().contains(text)
+}
+
+pub fn filter_cards<'a, 'b>(deck: &'a [Card<'b>], text: &str) -> Vec<&'a Card<'b>> {
+    let matches = text_contains(text);
+    deck.iter().filter(|d| matches(d)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_and_borrowed_cards_round_trip() {
+        let name = String::from("Kingsbane");
+        let text = String::from("Deathrattle: Shuffle this into your deck.");
+        let runtime = Card::Weapon {
+            id: 48353,
+            name: Cow::Borrowed(&name),
+            mana_cost: 1,
+            attack: 1,
+            durability: 4,
+            classes: vec![Class::Rogue],
+            rarity: Rarity::Legendary,
+            set: CardSet::KoboldsAndCatacombs,
+            text: Cow::Borrowed(&text),
+        };
+
+        let owned: OwnedCard = runtime.clone().into_owned();
+        drop((name, text));
+
+        assert_eq!(owned.borrowed(), owned);
+        assert_eq!(filter_cards(&[owned.borrowed()], "Deathrattle").len(), 1);
+        assert_eq!(
+            filter_cards(std::slice::from_ref(&owned), "Battlecry").len(),
+            0
+        );
+    }
 }
@@ -0,0 +1,219 @@
+//! Card names and text in the languages our players' clients use.
+//!
+//! A [`Card`] carries its enUS strings. [`Translations`] stores the other locales by card id, and
+//! anything it lacks falls back to enUS, so a card with only a German name still shows its English
+//! text in deDE. The class, tribe and spell school names are translated with `localized_name`.
+
+use std::collections::HashMap;
+
+use crate::{Card, CardInfo, CardKind, Class, FlavorText, MinionType, SpellSchool};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    EnUs,
+    DeDe,
+    TrTr,
+    KoKr,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct CardStrings {
+    name: Option<String>,
+    text: Option<String>,
+}
+
+/// Per-locale names and text, keyed by card id.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Translations {
+    strings: HashMap<(u32, Locale), CardStrings>,
+}
+
+impl Translations {
+    pub fn new() -> Translations {
+        Translations::default()
+    }
+
+    /// Sets the name card `id` shows in `locale`. Setting it for enUS overrides the card's own.
+    pub fn set_name(&mut self, id: u32, locale: Locale, name: impl Into<String>) {
+        self.strings.entry((id, locale)).or_default().name = Some(name.into());
+    }
+
+    pub fn set_text(&mut self, id: u32, locale: Locale, text: impl Into<String>) {
+        self.strings.entry((id, locale)).or_default().text = Some(text.into());
+    }
+
+    /// The card's name in `locale`, or its enUS name if there is no translation.
+    pub fn name<'c>(&'c self, card: &'c Card, locale: Locale) -> &'c str {
+        self.strings
+            .get(&(card.id(), locale))
+            .and_then(|s| s.name.as_deref())
+            .unwrap_or(card.name())
+    }
+
+    /// The card's text in `locale`, or its enUS text if there is no translation.
+    pub fn text<'c>(&'c self, card: &'c Card, locale: Locale) -> &'c str {
+        self.strings
+            .get(&(card.id(), locale))
+            .and_then(|s| s.text.as_deref())
+            .unwrap_or(card.text())
+    }
+
+    /// A view of `card` whose [`FlavorText::text`] and [`CardInfo::name`] are in `locale`.
+    pub fn localize<'c>(&'c self, card: &'c Card<'c>, locale: Locale) -> Localized<'c> {
+        Localized {
+            card,
+            locale,
+            translations: self,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// A card seen through a client in one locale.
+#[derive(Debug, Clone, Copy)]
+pub struct Localized<'c> {
+    card: &'c Card<'c>,
+    locale: Locale,
+    translations: &'c Translations,
+}
+
+impl<'c> Localized<'c> {
+    pub fn card(&self) -> &'c Card<'c> {
+        self.card
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+}
+
+impl FlavorText for Localized<'_> {
+    fn text(&self) -> &str {
+        self.translations.text(self.card, self.locale)
+    }
+}
+
+impl CardInfo for Localized<'_> {
+    fn name(&self) -> &str {
+        self.translations.name(self.card, self.locale)
+    }
+
+    fn mana_cost(&self) -> u8 {
+        self.card.mana_cost()
+    }
+
+    fn class(&self) -> Class {
+        self.card.class()
+    }
+
+    fn kind(&self) -> CardKind {
+        self.card.kind()
+    }
+
+    fn minion_type(&self) -> Option<MinionType> {
+        self.card.minion_type()
+    }
+
+    fn spell_school(&self) -> Option<SpellSchool> {
+        self.card.spell_school()
+    }
+}
+
+/// Adds `localized_name`, falling back to the enUS `name` for enUS itself.
+macro_rules! localized_names {
+    ($ty:ident, { $($variant:ident => [$de:literal, $tr:literal, $ko:literal]),* $(,)? }) => {
+        impl $ty {
+            /// The name a client in `locale` shows.
+            pub fn localized_name(self, locale: Locale) -> &'static str {
+                match locale {
+                    Locale::EnUs => self.name(),
+                    Locale::DeDe => match self {
+                        $($ty::$variant => $de),*
+                    },
+                    Locale::TrTr => match self {
+                        $($ty::$variant => $tr),*
+                    },
+                    Locale::KoKr => match self {
+                        $($ty::$variant => $ko),*
+                    },
+                }
+            }
+        }
+    };
+}
+
+localized_names!(Class, {
+    DeathKnight => ["Todesritter", "Ölüm Şövalyesi", "죽음의 기사"],
+    DemonHunter => ["Dämonenjäger", "İblis Avcısı", "악마사냥꾼"],
+    Druid => ["Druide", "Druid", "드루이드"],
+    Hunter => ["Jäger", "Avcı", "사냥꾼"],
+    Mage => ["Magier", "Büyücü", "마법사"],
+    Paladin => ["Paladin", "Paladin", "성기사"],
+    Priest => ["Priester", "Rahip", "사제"],
+    Rogue => ["Schurke", "Haydut", "도적"],
+    Shaman => ["Schamane", "Şaman", "주술사"],
+    Warlock => ["Hexenmeister", "Kara Büyücü", "흑마법사"],
+    Warrior => ["Krieger", "Savaşçı", "전사"],
+    Neutral => ["Neutral", "Tarafsız", "중립"],
+});
+
+localized_names!(MinionType, {
+    Beast => ["Wildtier", "Canavar", "야수"],
+    Demon => ["Dämon", "İblis", "악마"],
+    Draenei => ["Draenei", "Draenei", "드레나이"],
+    Dragon => ["Drache", "Ejderha", "용족"],
+    Elemental => ["Elementar", "Elemental", "정령"],
+    Mech => ["Mech", "Mekanik", "기계"],
+    Murloc => ["Murloc", "Murloc", "멀록"],
+    Naga => ["Naga", "Naga", "나가"],
+    Pirate => ["Pirat", "Korsan", "해적"],
+    Quilboar => ["Stacheleber", "Domuzadam", "가시멧돼지"],
+    Totem => ["Totem", "Totem", "토템"],
+    Undead => ["Untot", "Hortlak", "언데드"],
+});
+
+localized_names!(SpellSchool, {
+    Arcane => ["Arkan", "Gizemli", "비전"],
+    Fel => ["Teufel", "Fel", "지옥"],
+    Fire => ["Feuer", "Ateş", "화염"],
+    Frost => ["Frost", "Buz", "냉기"],
+    Holy => ["Heilig", "Kutsal", "신성"],
+    Nature => ["Natur", "Doğa", "자연"],
+    Shadow => ["Schatten", "Gölge", "암흑"],
+    General => ["Allgemein", "Genel", "일반"],
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CardDb;
+
+    #[test]
+    fn falls_back_to_en_us() {
+        let csv = "type,id,name,name_deDE,name_koKR,text_koKR,mana_cost,class,rarity,set,attack,health,text\n\
+                   minion,3,Edwin van Cleef,Edwin van Cleef,에드윈 밴클리프,\"연계: +2/+2를 얻습니다.\",3,Rogue,Legendary,Legacy,2,2,\"Combo: Gain +2/+2.\"\n\
+                   minion,4,Patches the Pirate,Käpt'n Flicken,,,1,Neutral,Legendary,Legacy,1,1,Charge\n";
+        let db = CardDb::from_csv(csv).unwrap();
+        let patches = db.by_id(4).unwrap();
+        let edwin = db.by_id(3).unwrap();
+
+        let german = db.translations().localize(patches, Locale::DeDe);
+        assert_eq!(german.name(), "Käpt'n Flicken");
+        assert_eq!(german.text(), "Charge");
+        assert_eq!(german.mana_cost(), 1);
+        let korean = db.translations().localize(edwin, Locale::KoKr);
+        assert_eq!(korean.text(), "연계: +2/+2를 얻습니다.");
+        assert_eq!(
+            db.translations().name(patches, Locale::TrTr),
+            "Patches the Pirate"
+        );
+
+        assert_eq!(Class::Rogue.localized_name(Locale::EnUs), "Rogue");
+        assert_eq!(Class::Rogue.localized_name(Locale::DeDe), "Schurke");
+        assert_eq!(MinionType::Pirate.localized_name(Locale::KoKr), "해적");
+        assert_eq!("kokr".parse(), Ok(Locale::KoKr));
+    }
+}
@@ -0,0 +1,218 @@
+//! Finding cards by a name the user typed, typos included.
+//!
+//! Names are compared after folding: lowercased, with accents removed and everything but letters
+//! and digits dropped, so "edwin vancleef", "Edwin VanCleef" and "Edwin Van-Cleef" are the same
+//! name. Matches are scored from 0 to 1: an exact name scores 1, a name starting with the query
+//! or containing it scores a little less, and anything else scores by edit distance, counting a
+//! swap of two neighbouring letters as one edit.
+
+use crate::{Card, CardInfo};
+
+/// Matches scoring below this are left out of [`NameIndex::lookup`].
+pub const MIN_SCORE: f64 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameMatch<'i> {
+    pub id: u32,
+    pub name: &'i str,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    id: u32,
+    name: String,
+    folded: String,
+    /// The folded form of each word, for completing a word in the middle of a name.
+    words: Vec<String>,
+}
+
+/// Card names prepared for fuzzy lookup and completion.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameIndex {
+    entries: Vec<Entry>,
+}
+
+impl NameIndex {
+    pub fn new(cards: &[Card]) -> NameIndex {
+        let mut index = NameIndex::default();
+        for card in cards {
+            index.insert(card.id(), card.name());
+        }
+        index
+    }
+
+    pub fn insert(&mut self, id: u32, name: &str) {
+        self.entries.push(Entry {
+            id,
+            name: name.to_string(),
+            folded: fold(name),
+            words: name.split_whitespace().map(fold).collect(),
+        });
+    }
+
+    /// The best `limit` matches for `query`, highest score first.
+    pub fn lookup(&self, query: &str, limit: usize) -> Vec<NameMatch<'_>> {
+        let query = fold(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<NameMatch> = self
+            .entries
+            .iter()
+            .map(|entry| NameMatch {
+                id: entry.id,
+                name: &entry.name,
+                score: score(&query, &entry.folded),
+            })
+            .filter(|m| m.score >= MIN_SCORE)
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.name.cmp(b.name)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Up to `limit` names that start with `prefix`, or have a word that does, shortest first.
+    /// Names starting with the prefix come before those that only match on a later word.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<&str> {
+        let prefix = fold(prefix);
+        let mut names: Vec<(bool, &str)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                if entry.folded.starts_with(&prefix) {
+                    Some((false, entry.name.as_str()))
+                } else if entry.words.iter().any(|w| w.starts_with(&prefix)) {
+                    Some((true, entry.name.as_str()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        names.sort_by_key(|&(later_word, name)| (later_word, name.chars().count(), name));
+        names.dedup_by_key(|&mut (_, name)| name);
+        names.truncate(limit);
+        names.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+/// Lowercases, strips accents and drops everything but letters and digits.
+pub fn fold(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars().flat_map(char::to_lowercase) {
+        let plain = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+            'æ' => "ae",
+            'ç' | 'ć' | 'č' => "c",
+            'ď' | 'đ' => "d",
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+            'ğ' => "g",
+            'ì' | 'í' | 'î' | 'ï' | 'ı' | 'ī' | 'į' => "i",
+            'ł' => "l",
+            'ñ' | 'ń' | 'ň' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+            'œ' => "oe",
+            'ř' => "r",
+            'ś' | 'š' | 'ş' => "s",
+            'ß' => "ss",
+            'ť' | 'ţ' => "t",
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+            'ý' | 'ÿ' => "y",
+            'ź' | 'ż' | 'ž' => "z",
+            c if c.is_alphanumeric() => {
+                out.push(c);
+                continue;
+            }
+            _ => continue,
+        };
+        out.push_str(plain);
+    }
+    out
+}
+
+fn score(query: &str, name: &str) -> f64 {
+    if query == name {
+        1.0
+    } else if name.starts_with(query) {
+        0.9
+    } else if name.contains(query) {
+        0.8
+    } else {
+        let longest = query.chars().count().max(name.chars().count());
+        1.0 - edit_distance(query, name) as f64 / longest as f64
+    }
+}
+
+/// Edits needed to turn `a` into `b`: insertions, deletions, substitutions and swaps of
+/// neighbouring characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> NameIndex {
+        let mut index = NameIndex::default();
+        for (id, name) in [
+            (3, "Edwin VanCleef"),
+            (48353, "Kingsbane"),
+            (1, "Kingsbane's Edge"),
+            (2, "Lúcio"),
+            (4, "Edwin, Defias Kingpin"),
+        ] {
+            index.insert(id, name);
+        }
+        index
+    }
+
+    #[test]
+    fn finds_names_despite_typos() {
+        let index = index();
+        assert_eq!(index.lookup("edwin vancleef", 1)[0].id, 3);
+        assert_eq!(index.lookup("edwin vancleef", 1)[0].score, 1.0);
+        let kingsbane = index.lookup("kingsbayne", 5);
+        assert_eq!(kingsbane[0].name, "Kingsbane");
+        assert!(kingsbane[0].score > 0.85);
+        assert_eq!(index.lookup("lucio", 1)[0].id, 2);
+        assert_eq!(index.lookup("kignsbane", 1)[0].id, 48353);
+        assert!(index.lookup("pyroblast", 5).is_empty());
+        assert_eq!(edit_distance("ab", "ba"), 1);
+    }
+
+    #[test]
+    fn completes_prefixes() {
+        let index = index();
+        assert_eq!(
+            index.complete("kings", 5),
+            ["Kingsbane", "Kingsbane's Edge"]
+        );
+        assert_eq!(
+            index.complete("ed", 5),
+            [
+                "Edwin VanCleef",
+                "Edwin, Defias Kingpin",
+                "Kingsbane's Edge"
+            ]
+        );
+        assert_eq!(index.complete("def", 5), ["Edwin, Defias Kingpin"]);
+    }
+}
@@ -1,49 +1,618 @@
-use get_rusty::FlavorText;
-use get_rusty::filter_cards;
-use get_rusty::{Card, Class, MinionType, SpellSchool};
-
-fn main() {
-    let custom_deck: Vec<Card> = vec![
-        Card::Minion {
-            name: "Edwin van Cleef",
-            mana_cost: 3,
-            minion_type: MinionType::Pirate,
-            class: Class::Rogue,
-            text: "Combo: Gain +2/+2 for each other card you've played this turn.",
-        },
-        Card::Hero {
-            name: "Deathwing, Worldbreaker",
-            mana_cost: 10,
-            class: Class::Neutral,
-            text: "Battlecry: Choose 1 Cataclysm to unleash! Herald twice to upgrade.",
-        },
-        Card::Weapon {
-            name: "Kingsbane",
-            mana_cost: 1,
-            class: Class::Rogue,
-            text: "Always keeps enhancements. Deathrattle: Shuffle this into your deck.",
-        },
-        Card::Location {
-            name: "Amirdrassil",
-            mana_cost: 5,
-            class: Class::Druid,
-            text: "Summon a 1-Cost minion. Gain 1 Armor. Draw 1 card. Refresh 1 Mana Crystal. (Improves each use!)",
-        },
-        Card::Spell {
-            name: "Preparation",
-            mana_cost: 0,
-            spell_school: SpellSchool::Shadow,
-            class: Class::Rogue,
-            text: "The next spell you cast this turn costs (2) less.",
-        },
-    ];
-
-    for card in &custom_deck {
-        println!("{}", card.text());
-    }
-
-    let result = filter_cards(&custom_deck, "Battlecry");
-    println!("{result:?}");
-    let result = filter_cards(&custom_deck, "Combo");
-    println!("{result:?}");
+//! A command-line deck tool.
+//!
+//! Every command reads a card pool (`--cards`, JSON or CSV) and prints a table or, with
+//! `--format json`, JSON for scripts. A deck argument is either a deck file as the game exports it
+//! or a bare deckstring. The exit code is 0 on success, 1 when `validate` finds violations and 2
+//! for usage, input and loading errors.
+
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::ExitCode;
+
+use get_rusty::json::Value;
+use get_rusty::{
+    Card, CardDb, CardInfo, Collection, Completion, CraftingPlan, Deck, DeckDiff, DeckStats,
+    GreedyBot, MatchJob, NameIndex, OwnedCard, Patch, Player, Pool, Replay, Similarity, Viewer,
+    cluster, run_matchups, search,
+};
+
+const USAGE: &str = "\
+usage: get_rusty [--cards <file>] [--format table|json] <command>
+
+commands:
+  decode <deckstring>   list the cards in a deck
+  search <query>        find cards, e.g. 'class:rogue mana<=2 keyword:combo'
+  find <name>           look a card up by name, allowing typos
+  stats <deck>          mana curve and card counts
+  validate <deck>       check the deck-building rules; exits 1 if any are broken
+  diff <a> <b>          compare two decks
+  patch <file> [deck]   a balance patch's changes, and how it affects each deck given
+  craft <coll> <deck>   cards missing from a collection and the dust to craft them
+  completion <coll>     share of each class and set a collection owns
+  simulate <n> <decks>  play n games between each pair of decks with greedy bots
+  cluster <k> <decks>   group decks into up to k archetypes by the cards they share
+  replay <file>         step through a recorded game, checking it still plays out the same
+
+A <deck> is a deck file exported by the game or a deckstring.
+A <coll> is a collection CSV with id (or name), count and optional finish columns.
+The card pool defaults to cards.json; files ending in .csv are read as CSV.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Table,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    cards: String,
+    output: Output,
+    command: Vec<String>,
+    /// Print the usage and exit successfully.
+    help: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        cards: "cards.json".to_string(),
+        output: Output::Table,
+        command: Vec::new(),
+        help: false,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or(format!("{flag} needs a value"));
+        match arg.as_str() {
+            "--cards" => options.cards = value("--cards")?,
+            "--format" => {
+                options.output = match value("--format")?.as_str() {
+                    "table" => Output::Table,
+                    "json" => Output::Json,
+                    other => return Err(format!("unknown output format \"{other}\"")),
+                }
+            }
+            "-h" | "--help" => {
+                options.help = true;
+                return Ok(options);
+            }
+            _ => options.command.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+fn load_cards(path: &str) -> Result<CardDb, String> {
+    let src = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let db = if path.ends_with(".csv") {
+        CardDb::from_csv(&src)
+    } else {
+        CardDb::from_json(&src)
+    };
+    db.map_err(|e| format!("{path}: {e}"))
+}
+
+/// Reads `arg` as a deck file if one exists at that path, and as a deckstring otherwise.
+fn load_deck(arg: &str, db: &CardDb) -> Result<Deck, String> {
+    let deck = if Path::new(arg).is_file() {
+        let src = fs::read_to_string(arg).map_err(|e| format!("{arg}: {e}"))?;
+        Deck::from_deck_file(&src, db)
+    } else {
+        Deck::from_deckstring(arg, db)
+    };
+    deck.map_err(|e| format!("{arg}: {e}"))
+}
+
+fn deck_json(deck: &Deck) -> Value {
+    Value::object([
+        ("format", Value::from(deck.format.name())),
+        ("class", Value::from(deck.class.name())),
+        ("hero", Value::from(deck.hero)),
+        ("deckstring", Value::from(deck.to_deckstring())),
+        (
+            "cards",
+            Value::array(deck.cards.iter().map(|(card, count)| {
+                Value::object([
+                    ("count", Value::from(*count as u32)),
+                    ("card", card.to_json()),
+                ])
+            })),
+        ),
+    ])
+}
+
+fn stats_json(stats: &DeckStats) -> Value {
+    fn counts<T: ToString>(counts: &[(T, usize)]) -> Value {
+        Value::object(
+            counts
+                .iter()
+                .map(|(value, count)| (value.to_string(), Value::from(*count))),
+        )
+    }
+    Value::object([
+        ("total", Value::from(stats.total)),
+        ("average_cost", Value::from(stats.average_cost)),
+        (
+            "mana_curve",
+            Value::array(stats.mana_curve.iter().map(|&n| Value::from(n))),
+        ),
+        ("by_kind", counts(&stats.by_kind)),
+        ("by_class", counts(&stats.by_class)),
+        ("by_spell_school", counts(&stats.by_spell_school)),
+        ("by_minion_type", counts(&stats.by_minion_type)),
+        ("by_keyword", counts(&stats.by_keyword)),
+    ])
+}
+
+fn diff_json(diff: &DeckDiff) -> Value {
+    let entry = |card: &Card, before: u8, after: u8| {
+        Value::object([
+            ("id", Value::from(card.id())),
+            ("name", Value::from(card.name())),
+            ("before", Value::from(before as u32)),
+            ("after", Value::from(after as u32)),
+        ])
+    };
+    Value::object([
+        (
+            "added",
+            Value::array(diff.added.iter().map(|(card, n)| entry(card, 0, *n))),
+        ),
+        (
+            "removed",
+            Value::array(diff.removed.iter().map(|(card, n)| entry(card, *n, 0))),
+        ),
+        (
+            "changed",
+            Value::array(diff.changed.iter().map(|(card, a, b)| entry(card, *a, *b))),
+        ),
+        (
+            "curve_delta",
+            Value::array(diff.curve_delta.iter().map(|&d| Value::from(d as f64))),
+        ),
+    ])
+}
+
+fn load_collection(path: &str, db: &CardDb) -> Result<Collection, String> {
+    Collection::load(path, db).map_err(|e| format!("{path}: {e}"))
+}
+
+fn craft_json(plan: &CraftingPlan) -> Value {
+    Value::object([
+        (
+            "missing",
+            Value::array(plan.missing.iter().map(|(card, count)| {
+                Value::object([
+                    ("id", Value::from(card.id())),
+                    ("name", Value::from(card.name())),
+                    ("count", Value::from(*count as u32)),
+                ])
+            })),
+        ),
+        (
+            "dust_by_rarity",
+            Value::object(
+                plan.dust_by_rarity
+                    .iter()
+                    .map(|(rarity, dust)| (rarity.to_string(), Value::from(*dust))),
+            ),
+        ),
+        ("total_dust", Value::from(plan.total_dust)),
+    ])
+}
+
+fn completion_json(completion: &Completion) -> Value {
+    fn percents<T: ToString>(percents: &[(T, f64)]) -> Value {
+        Value::object(
+            percents
+                .iter()
+                .map(|(value, percent)| (value.to_string(), Value::from(*percent))),
+        )
+    }
+    Value::object([
+        ("by_class", percents(&completion.by_class)),
+        ("by_set", percents(&completion.by_set)),
+    ])
+}
+
+fn run(options: &Options) -> Result<ExitCode, String> {
+    if options.help {
+        println!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let json = options.output == Output::Json;
+    let args: Vec<&str> = options.command.iter().map(String::as_str).collect();
+    let (command, args) = args.split_first().ok_or(String::new())?;
+    // Each command reads the card pool once its arguments have matched, so usage mistakes are
+    // reported whether or not the pool can be read.
+    let load = || load_cards(&options.cards);
+
+    match (*command, args) {
+        ("decode", [code]) => {
+            let db = load()?;
+            let deck = Deck::from_deckstring(code, &db).map_err(|e| e.to_string())?;
+            if json {
+                println!("{:#}", deck_json(&deck));
+            } else {
+                print!("{deck}");
+            }
+        }
+        ("search", [query]) => {
+            let db = load()?;
+            let cards = search(db.cards(), query).map_err(|e| e.to_string())?;
+            if json {
+                println!(
+                    "{:#}",
+                    Value::array(cards.iter().map(|card| card.to_json()))
+                );
+            } else {
+                for card in cards {
+                    println!("{card}");
+                }
+            }
+        }
+        ("find", [name]) => {
+            let db = load()?;
+            let index = NameIndex::new(db.cards());
+            let matches = index.lookup(name, 5);
+            if json {
+                println!(
+                    "{:#}",
+                    Value::array(matches.iter().map(|m| Value::object([
+                        ("id", Value::from(m.id)),
+                        ("name", Value::from(m.name)),
+                        ("score", Value::from(m.score)),
+                    ])))
+                );
+            } else {
+                for m in matches {
+                    println!("{:>3.0}% {} ({})", m.score * 100.0, m.name, m.id);
+                }
+            }
+        }
+        ("stats", [deck]) => {
+            let db = load()?;
+            let stats = load_deck(deck, &db)?.stats();
+            if json {
+                println!("{:#}", stats_json(&stats));
+            } else {
+                print!("{stats}");
+            }
+        }
+        ("validate", [deck]) => {
+            let db = load()?;
+            let violations = load_deck(deck, &db)?.validate().err().unwrap_or_default();
+            if json {
+                let report = Value::object([
+                    ("valid", Value::from(violations.is_empty())),
+                    (
+                        "violations",
+                        Value::array(violations.iter().map(|v| Value::from(v.to_string()))),
+                    ),
+                ]);
+                println!("{report:#}");
+            } else if violations.is_empty() {
+                println!("deck is valid");
+            } else {
+                for violation in &violations {
+                    println!("{violation}");
+                }
+            }
+            if !violations.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        ("diff", [a, b]) => {
+            let db = load()?;
+            let (a, b) = (load_deck(a, &db)?, load_deck(b, &db)?);
+            let diff = a.diff(&b);
+            if json {
+                println!("{:#}", diff_json(&diff));
+            } else {
+                print!("{diff}");
+            }
+        }
+        ("patch", [patch, decks @ ..]) => {
+            let db = load()?;
+            let src = fs::read_to_string(patch).map_err(|e| format!("{patch}: {e}"))?;
+            let patch = Patch::from_json(&src).map_err(|e| format!("{patch}: {e}"))?;
+            let loaded = decks
+                .iter()
+                .map(|deck| load_deck(deck, &db))
+                .collect::<Result<Vec<_>, _>>()?;
+            let log = patch.changelog(&db);
+            let reviews = patch.review(&loaded);
+            if json {
+                let report = Value::object([
+                    (
+                        "changes",
+                        Value::array(log.entries.iter().map(|entry| {
+                            Value::object([
+                                ("id", Value::from(entry.id)),
+                                ("name", Value::from(entry.name.as_str())),
+                                ("field", Value::from(entry.field.to_string())),
+                                ("before", Value::from(entry.before.as_str())),
+                                ("after", Value::from(entry.after.as_str())),
+                            ])
+                        })),
+                    ),
+                    (
+                        "decks",
+                        Value::array(reviews.iter().map(|review| {
+                            let ids =
+                                |ids: &[u32]| Value::array(ids.iter().map(|&id| Value::from(id)));
+                            Value::object([
+                                ("deck", Value::from(decks[review.index])),
+                                ("nerfed", ids(&review.nerfed)),
+                                ("buffed", ids(&review.buffed)),
+                                (
+                                    "violations",
+                                    Value::array(
+                                        review
+                                            .violations
+                                            .iter()
+                                            .map(|v| Value::from(v.to_string())),
+                                    ),
+                                ),
+                            ])
+                        })),
+                    ),
+                ]);
+                println!("{report:#}");
+            } else {
+                print!("{log}");
+                for review in &reviews {
+                    println!(
+                        "{}: {} nerfed, {} buffed",
+                        decks[review.index],
+                        review.nerfed.len(),
+                        review.buffed.len()
+                    );
+                    for violation in &review.violations {
+                        println!("  {violation}");
+                    }
+                }
+            }
+        }
+        ("craft", [collection, deck]) => {
+            let db = load()?;
+            let collection = load_collection(collection, &db)?;
+            let deck = load_deck(deck, &db)?;
+            let cards = deck.expand();
+            let plan = collection.crafting_plan(&cards);
+            if json {
+                println!("{:#}", craft_json(&plan));
+            } else {
+                print!("{plan}");
+            }
+        }
+        ("completion", [collection]) => {
+            let db = load()?;
+            let completion = load_collection(collection, &db)?.completion(db.cards());
+            if json {
+                println!("{:#}", completion_json(&completion));
+            } else {
+                print!("{completion}");
+            }
+        }
+        ("simulate", [games, decks @ ..]) if decks.len() >= 2 => {
+            let db = load()?;
+            let games: u32 = games
+                .parse()
+                .map_err(|_| format!("invalid number of games \"{games}\""))?;
+            let loaded = decks
+                .iter()
+                .map(|deck| {
+                    let deck = load_deck(deck, &db)?;
+                    Ok(deck.expand().into_iter().map(Card::into_owned).collect())
+                })
+                .collect::<Result<Vec<Vec<OwnedCard>>, String>>()?;
+            let mut pairs = Vec::new();
+            for a in 0..decks.len() {
+                for b in a + 1..decks.len() {
+                    pairs.push((a, b));
+                }
+            }
+            let matchups = pairs
+                .iter()
+                .map(|&(a, b)| [loaded[a].clone(), loaded[b].clone()])
+                .collect();
+            let greedy = |_: &MatchJob| -> [Box<dyn Player>; 2] {
+                [Box::new(GreedyBot), Box::new(GreedyBot)]
+            };
+            let reports = run_matchups(&Pool::available(), matchups, games, 0, greedy);
+            if json {
+                let report = Value::array(pairs.iter().zip(&reports).map(|(&(a, b), report)| {
+                    let (low, high) = report.confidence_interval(0);
+                    Value::object([
+                        ("deck", Value::from(decks[a])),
+                        ("opponent", Value::from(decks[b])),
+                        ("games", Value::from(report.games)),
+                        ("wins", Value::from(report.wins[0])),
+                        ("losses", Value::from(report.wins[1])),
+                        ("draws", Value::from(report.draws)),
+                        ("win_rate", Value::from(report.win_rate(0))),
+                        (
+                            "interval",
+                            Value::array([Value::from(low), Value::from(high)]),
+                        ),
+                    ])
+                }));
+                println!("{report:#}");
+            } else {
+                for (&(a, b), report) in pairs.iter().zip(&reports) {
+                    let (low, high) = report.confidence_interval(0);
+                    println!(
+                        "{} vs {}: {}-{}-{}, {:.1}% (95% CI {:.1}%-{:.1}%)",
+                        decks[a],
+                        decks[b],
+                        report.wins[0],
+                        report.wins[1],
+                        report.draws,
+                        report.win_rate(0) * 100.0,
+                        low * 100.0,
+                        high * 100.0
+                    );
+                }
+            }
+        }
+        ("cluster", [k, decks @ ..]) if !decks.is_empty() => {
+            let db = load()?;
+            let k: usize = k
+                .parse()
+                .map_err(|_| format!("invalid number of archetypes \"{k}\""))?;
+            let loaded = decks
+                .iter()
+                .map(|deck| load_deck(deck, &db))
+                .collect::<Result<Vec<_>, _>>()?;
+            let archetypes = cluster(&loaded, k, Similarity::Cosine);
+            let name = |id: u32| {
+                db.by_id(id)
+                    .map_or(id.to_string(), |c| c.name().to_string())
+            };
+            if json {
+                let report = Value::array(archetypes.iter().map(|archetype| {
+                    Value::object([
+                        ("label", Value::from(archetype.label.as_str())),
+                        (
+                            "decks",
+                            Value::array(archetype.decks.iter().map(|&i| Value::from(decks[i]))),
+                        ),
+                        ("medoid", Value::from(decks[archetype.medoid])),
+                        (
+                            "distinctive",
+                            Value::array(
+                                archetype
+                                    .distinctive
+                                    .iter()
+                                    .map(|&id| Value::from(name(id))),
+                            ),
+                        ),
+                    ])
+                }));
+                println!("{report:#}");
+            } else {
+                for archetype in &archetypes {
+                    println!("{archetype}");
+                    let cards: Vec<String> =
+                        archetype.distinctive.iter().map(|&id| name(id)).collect();
+                    println!("  key cards: {}", cards.join(", "));
+                    for &i in &archetype.decks {
+                        println!("  {}", decks[i]);
+                    }
+                }
+            }
+        }
+        ("replay", [file]) => {
+            let db = load()?;
+            let src = fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?;
+            let replay = Replay::parse(&src).map_err(|e| format!("{file}: {e}"))?;
+            let mut viewer = Viewer::new(&replay, &db).map_err(|e| format!("{file}: {e}"))?;
+            // Wait for Enter between actions only when someone is watching.
+            let interactive = !json && io::stdin().is_terminal();
+            if !json {
+                print!("{}", viewer.render());
+            }
+            while let Some(step) = viewer.step() {
+                let step = step.map_err(|e| format!("{file}: {e}"))?;
+                if json {
+                    continue;
+                }
+                if interactive {
+                    io::stdin()
+                        .read_line(&mut String::new())
+                        .map_err(|e| e.to_string())?;
+                }
+                println!("\n> {:?}", step.action);
+                print!("{}", viewer.render());
+            }
+            if json {
+                let game = viewer.game();
+                let report = Value::object([
+                    ("actions", Value::from(replay.steps.len() as u32)),
+                    ("turn", Value::from(game.turn())),
+                    (
+                        "state_hash",
+                        Value::from(format!("{:016x}", game.state_hash())),
+                    ),
+                ]);
+                println!("{report:#}");
+            }
+        }
+        _ => return Err(String::new()),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let result = parse_args(std::env::args().skip(1)).and_then(|options| run(&options));
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            if message.is_empty() {
+                eprintln!("{USAGE}");
+            } else {
+                eprintln!("error: {message}");
+            }
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_around_the_command() {
+        let options =
+            parse(&["stats", "--format", "json", "deck.txt", "--cards", "a.csv"]).unwrap();
+        assert_eq!(
+            options,
+            Options {
+                cards: "a.csv".to_string(),
+                output: Output::Json,
+                command: vec!["stats".to_string(), "deck.txt".to_string()],
+                help: false,
+            }
+        );
+        assert!(
+            parse(&["search", "--help", "--format", "xml"])
+                .unwrap()
+                .help
+        );
+        assert_eq!(
+            parse(&["--format", "xml"]).err().as_deref(),
+            Some("unknown output format \"xml\"")
+        );
+        assert_eq!(
+            parse(&["decode", "--cards"]).err().as_deref(),
+            Some("--cards needs a value")
+        );
+    }
+
+    #[test]
+    fn usage_errors_come_before_loading_cards() {
+        for command in [
+            &["frobnicate"][..],
+            &["diff", "a"],
+            &["simulate", "10", "a"],
+            &[],
+        ] {
+            let mut args = vec!["--cards", "/nonexistent/cards.json"];
+            args.extend(command);
+            assert_eq!(
+                run(&parse(&args).unwrap()),
+                Err(String::new()),
+                "{command:?}"
+            );
+        }
+        let err = run(&parse(&["decode", "x", "--cards", "/nonexistent/cards.json"]).unwrap());
+        assert!(err.unwrap_err().starts_with("/nonexistent/cards.json: "));
+        assert_eq!(run(&parse(&["--help"]).unwrap()), Ok(ExitCode::SUCCESS));
+    }
 }
@@ -0,0 +1,155 @@
+//! Renders card text markup for display.
+//!
+//! Card text uses `<b>` and `<i>` tags, `[x]` layout hints, and two number placeholders: `$N` is
+//! damage that spell damage adds to, and `#N` is healing that healing bonuses add to. A value
+//! changed by a modifier is highlighted, the way the game client colors it. Line breaks are kept.
+
+use crate::FlavorText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// Text only, without tags or escape codes.
+    Plain,
+    /// Bold and italic escape codes, with changed numbers in green (up) or red (down).
+    Ansi,
+    /// Escaped HTML with `<b>`, `<i>`, `<br>` and changed numbers in `<span class="increased">` or
+    /// `<span class="decreased">`.
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Renderer {
+    pub style: Style,
+    /// Added to every `$N`.
+    pub spell_damage: i32,
+    /// Added to every `#N`.
+    pub healing: i32,
+}
+
+impl Renderer {
+    /// A renderer with no modifiers, so placeholders show their printed values.
+    pub fn new(style: Style) -> Renderer {
+        Renderer {
+            style,
+            spell_damage: 0,
+            healing: 0,
+        }
+    }
+
+    /// Renders a card's text, in whichever locale `card` gives it.
+    pub fn card(&self, card: &impl FlavorText) -> String {
+        self.render(card.text())
+    }
+
+    pub fn render(&self, text: &str) -> String {
+        let text = text.replace("[x]", "");
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '<' => {
+                    let mut tag = String::new();
+                    for c in chars.by_ref() {
+                        if c == '>' {
+                            break;
+                        }
+                        tag.push(c);
+                    }
+                    self.tag(&mut out, &tag.trim().to_lowercase());
+                }
+                '$' | '#' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                    let mut digits = String::new();
+                    while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(d);
+                    }
+                    let printed: i32 = digits.parse().unwrap_or(i32::MAX);
+                    let bonus = if c == '$' {
+                        self.spell_damage
+                    } else {
+                        self.healing
+                    };
+                    self.number(&mut out, printed, printed.saturating_add(bonus).max(0));
+                }
+                '\n' if self.style == Style::Html => out.push_str("<br>"),
+                '&' if self.style == Style::Html => out.push_str("&amp;"),
+                '>' if self.style == Style::Html => out.push_str("&gt;"),
+                '"' if self.style == Style::Html => out.push_str("&quot;"),
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// Writes a `<b>` or `<i>` tag; any other tag is dropped.
+    fn tag(&self, out: &mut String, tag: &str) {
+        let code = match (self.style, tag) {
+            (Style::Plain, _) => return,
+            (Style::Ansi, "b") => "\x1b[1m",
+            (Style::Ansi, "/b") => "\x1b[22m",
+            (Style::Ansi, "i") => "\x1b[3m",
+            (Style::Ansi, "/i") => "\x1b[23m",
+            (Style::Html, "b" | "/b" | "i" | "/i") => {
+                out.push_str(&format!("<{tag}>"));
+                return;
+            }
+            _ => return,
+        };
+        out.push_str(code);
+    }
+
+    fn number(&self, out: &mut String, printed: i32, value: i32) {
+        let (ansi, class) = match value.cmp(&printed) {
+            std::cmp::Ordering::Equal => {
+                out.push_str(&value.to_string());
+                return;
+            }
+            std::cmp::Ordering::Greater => ("\x1b[32m", "increased"),
+            std::cmp::Ordering::Less => ("\x1b[31m", "decreased"),
+        };
+        match self.style {
+            Style::Plain => out.push_str(&value.to_string()),
+            Style::Ansi => out.push_str(&format!("{ansi}{value}\x1b[39m")),
+            Style::Html => out.push_str(&format!("<span class=\"{class}\">{value}</span>")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "[x]<b>Battlecry:</b> Deal $3 damage.\nRestore #2 Health & <i>draw</i>.";
+
+    #[test]
+    fn renders_each_style() {
+        let plain = Renderer {
+            spell_damage: 1,
+            ..Renderer::new(Style::Plain)
+        };
+        assert_eq!(
+            plain.render(TEXT),
+            "Battlecry: Deal 4 damage.\nRestore 2 Health & draw."
+        );
+
+        let ansi = Renderer {
+            healing: -5,
+            ..Renderer::new(Style::Ansi)
+        };
+        assert_eq!(
+            ansi.render(TEXT),
+            "\x1b[1mBattlecry:\x1b[22m Deal 3 damage.\n\
+             Restore \x1b[31m0\x1b[39m Health & \x1b[3mdraw\x1b[23m."
+        );
+
+        let html = Renderer {
+            spell_damage: 2,
+            ..Renderer::new(Style::Html)
+        };
+        assert_eq!(
+            html.render(TEXT),
+            "<b>Battlecry:</b> Deal <span class=\"increased\">5</span> damage.<br>\
+             Restore 2 Health &amp; <i>draw</i>."
+        );
+        assert_eq!(html.render("#hashtag $ 1"), "#hashtag $ 1");
+    }
+}
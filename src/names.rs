@@ -0,0 +1,256 @@
+//! Display names for the card enums, and parsing them back.
+//!
+//! Parsing ignores case, spaces and punctuation, and accepts both the display name and the
+//! variant name, so "Death Knight", "DEATH_KNIGHT" and "DeathKnight" all give
+//! `Class::DeathKnight`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::collection::Finish;
+use crate::{
+    Card, CardInfo, CardKind, CardSet, Class, FlavorText, Format, Locale, MinionType, Rarity,
+    SpellSchool,
+};
+
+/// A name that doesn't match any variant of the enum being parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNameError {
+    pub what: &'static str,
+    pub value: String,
+}
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown {} \"{}\"", self.what, self.value)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+/// Lowercases and drops everything but letters and digits.
+pub(crate) fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+macro_rules! names {
+    ($ty:ident, $what:literal, { $($variant:ident => $name:literal),* $(,)? }) => {
+        impl $ty {
+            pub const ALL: &'static [$ty] = &[$($ty::$variant),*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($ty::$variant => $name),*
+                }
+            }
+        }
+
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.pad(self.name())
+            }
+        }
+
+        impl FromStr for $ty {
+            type Err = ParseNameError;
+
+            fn from_str(s: &str) -> Result<$ty, ParseNameError> {
+                let key = normalize(s);
+                $ty::ALL
+                    .iter()
+                    .copied()
+                    .find(|v| normalize(v.name()) == key || normalize(&format!("{v:?}")) == key)
+                    .ok_or_else(|| ParseNameError {
+                        what: $what,
+                        value: s.to_string(),
+                    })
+            }
+        }
+    };
+}
+
+names!(CardKind, "card type", {
+    Spell => "Spell",
+    Minion => "Minion",
+    Location => "Location",
+    Weapon => "Weapon",
+    Hero => "Hero",
+});
+
+names!(Class, "class", {
+    DeathKnight => "Death Knight",
+    DemonHunter => "Demon Hunter",
+    Druid => "Druid",
+    Hunter => "Hunter",
+    Mage => "Mage",
+    Paladin => "Paladin",
+    Priest => "Priest",
+    Rogue => "Rogue",
+    Shaman => "Shaman",
+    Warlock => "Warlock",
+    Warrior => "Warrior",
+    Neutral => "Neutral",
+});
+
+names!(MinionType, "minion type", {
+    Beast => "Beast",
+    Demon => "Demon",
+    Draenei => "Draenei",
+    Dragon => "Dragon",
+    Elemental => "Elemental",
+    Mech => "Mech",
+    Murloc => "Murloc",
+    Naga => "Naga",
+    Pirate => "Pirate",
+    Quilboar => "Quilboar",
+    Totem => "Totem",
+    Undead => "Undead",
+});
+
+names!(SpellSchool, "spell school", {
+    Arcane => "Arcane",
+    Fel => "Fel",
+    Fire => "Fire",
+    Frost => "Frost",
+    Holy => "Holy",
+    Nature => "Nature",
+    Shadow => "Shadow",
+    General => "General",
+});
+
+names!(Rarity, "rarity", {
+    Free => "Free",
+    Common => "Common",
+    Rare => "Rare",
+    Epic => "Epic",
+    Legendary => "Legendary",
+});
+
+names!(Finish, "finish", {
+    Normal => "Normal",
+    Golden => "Golden",
+    Signature => "Signature",
+});
+
+names!(Format, "format", {
+    Wild => "Wild",
+    Standard => "Standard",
+    Classic => "Classic",
+    Twist => "Twist",
+});
+
+names!(Locale, "locale", {
+    EnUs => "enUS",
+    DeDe => "deDE",
+    TrTr => "trTR",
+    KoKr => "koKR",
+});
+
+names!(CardSet, "card set", {
+    Legacy => "Legacy",
+    Core => "Core",
+    Classic => "Classic",
+    Naxxramas => "Curse of Naxxramas",
+    GoblinsVsGnomes => "Goblins vs Gnomes",
+    BlackrockMountain => "Blackrock Mountain",
+    TheGrandTournament => "The Grand Tournament",
+    LeagueOfExplorers => "The League of Explorers",
+    WhispersOfTheOldGods => "Whispers of the Old Gods",
+    OneNightInKarazhan => "One Night in Karazhan",
+    MeanStreetsOfGadgetzan => "Mean Streets of Gadgetzan",
+    JourneyToUnGoro => "Journey to Un'Goro",
+    KnightsOfTheFrozenThrone => "Knights of the Frozen Throne",
+    KoboldsAndCatacombs => "Kobolds & Catacombs",
+    TheWitchwood => "The Witchwood",
+    TheBoomsdayProject => "The Boomsday Project",
+    RastakhansRumble => "Rastakhan's Rumble",
+    RiseOfShadows => "Rise of Shadows",
+    SaviorsOfUldum => "Saviors of Uldum",
+    DescentOfDragons => "Descent of Dragons",
+    AshesOfOutland => "Ashes of Outland",
+    ScholomanceAcademy => "Scholomance Academy",
+    MadnessAtTheDarkmoonFaire => "Madness at the Darkmoon Faire",
+    ForgedInTheBarrens => "Forged in the Barrens",
+    UnitedInStormwind => "United in Stormwind",
+    FracturedInAlteracValley => "Fractured in Alterac Valley",
+    VoyageToTheSunkenCity => "Voyage to the Sunken City",
+    MurderAtCastleNathria => "Murder at Castle Nathria",
+    MarchOfTheLichKing => "March of the Lich King",
+    FestivalOfLegends => "Festival of Legends",
+    Titans => "TITANS",
+    ShowdownInTheBadlands => "Showdown in the Badlands",
+    WhizbangsWorkshop => "Whizbang's Workshop",
+    PerilsInParadise => "Perils in Paradise",
+    TheGreatDarkBeyond => "The Great Dark Beyond",
+    IntoTheEmeraldDream => "Into the Emerald Dream",
+    TheLostCityOfUnGoro => "The Lost City of Un'Goro",
+    AcrossTheTimeways => "Across the Timeways",
+});
+
+/// Joins names with " / ", as in "Rogue / Warrior".
+pub(crate) fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+impl fmt::Display for Card<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Name: {} | Mana: {}", self.name(), self.mana_cost())?;
+        match self {
+            Card::Spell { spell_school, .. } => write!(f, " | Spell school: {spell_school}")?,
+            Card::Minion {
+                attack,
+                health,
+                minion_types,
+                ..
+            } => write!(
+                f,
+                " | Stats: {attack}/{health} | Minion type: {}",
+                join(minion_types)
+            )?,
+            Card::Location { durability, .. } => write!(f, " | Durability: {durability}")?,
+            Card::Weapon {
+                attack, durability, ..
+            } => write!(f, " | Stats: {attack}/{durability}")?,
+            Card::Hero { armor, .. } => write!(f, " | Armor: {armor}")?,
+        }
+        write!(
+            f,
+            " | Class: {} | Text: {}",
+            join(self.classes()),
+            self.text()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        assert_eq!(Class::DeathKnight.to_string(), "Death Knight");
+        assert_eq!("death knight".parse(), Ok(Class::DeathKnight));
+        assert_eq!("DEATH_KNIGHT".parse(), Ok(Class::DeathKnight));
+        assert_eq!(
+            "kobolds and catacombs".parse(),
+            Ok(CardSet::KoboldsAndCatacombs)
+        );
+        for set in CardSet::ALL {
+            assert_eq!(set.to_string().parse(), Ok(*set));
+        }
+        assert_eq!(
+            "Rouge".parse::<Class>(),
+            Err(ParseNameError {
+                what: "class",
+                value: "Rouge".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Odds of drawing target cards by a given turn.
+//!
+//! A target is any predicate over cards, such as [`text_contains`](crate::text_contains) (what
+//! [`filter_cards`](crate::filter_cards) matches on) or a parsed [`Query`](crate::query::Query).
+//! The exact calculation follows the hand through the mulligan with hypergeometric draws; the
+//! Monte Carlo one shuffles and deals real hands with a seeded [`Rng`] to cross-check it.
+//!
+//! Both players draw at the start of each of their turns, so by turn `n` the player going first
+//! has seen `3 + n` cards and the player going second `4 + n`. The Coin is not part of the deck,
+//! but it sits in the second player's hand and counts as a hit if the target matches it.
+
+use crate::game::the_coin;
+use crate::rng::Rng;
+use crate::{Card, CardInfo};
+
+/// Whether the player goes first or second (and so holds The Coin).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Play {
+    First,
+    Second,
+}
+
+impl Play {
+    /// Cards dealt before the mulligan.
+    pub fn opening_hand(self) -> usize {
+        match self {
+            Play::First => 3,
+            Play::Second => 4,
+        }
+    }
+}
+
+/// Which opening cards to keep; everything else is shuffled back after drawing replacements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mulligan {
+    KeepAll,
+    /// Keep only target cards.
+    FindTargets,
+    /// Keep target cards and anything costing at most this much mana.
+    KeepUpTo(u8),
+}
+
+impl Mulligan {
+    fn keeps(self, card: &Card, is_target: bool) -> bool {
+        match self {
+            Mulligan::KeepAll => true,
+            Mulligan::FindTargets => is_target,
+            Mulligan::KeepUpTo(cost) => is_target || card.mana_cost() <= cost,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Scenario {
+    pub play: Play,
+    /// The player's own turn number; 0 means the opening hand after the mulligan.
+    pub turn: u32,
+    pub mulligan: Mulligan,
+    /// How many target cards must be in hand to count as a hit.
+    pub copies: usize,
+}
+
+impl Scenario {
+    /// At least one target by `turn`, keeping only targets in the mulligan.
+    pub fn new(play: Play, turn: u32) -> Scenario {
+        Scenario {
+            play,
+            turn,
+            mulligan: Mulligan::FindTargets,
+            copies: 1,
+        }
+    }
+
+    fn coin_hits(&self, target: &impl Fn(&Card) -> bool) -> usize {
+        usize::from(self.play == Play::Second && target(&the_coin()))
+    }
+}
+
+fn choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Probability of exactly `hits` successes in `draws` cards from `size` cards holding `successes`.
+fn hypergeometric(size: usize, successes: usize, draws: usize, hits: usize) -> f64 {
+    if successes > size || draws > size || hits > draws {
+        return 0.0;
+    }
+    choose(successes, hits) * choose(size - successes, draws - hits) / choose(size, draws)
+}
+
+/// The exact probability of holding at least `scenario.copies` target cards by `scenario.turn`.
+pub fn draw_probability(deck: &[Card], target: impl Fn(&Card) -> bool, scenario: &Scenario) -> f64 {
+    let size = deck.len();
+    let hand = scenario.play.opening_hand().min(size);
+    let needed = scenario.copies.saturating_sub(scenario.coin_hits(&target));
+
+    // Cards split four ways: targets kept, targets thrown back, others kept, others thrown back.
+    let mut groups = [0; 4];
+    for card in deck {
+        let is_target = target(card);
+        let keep = scenario.mulligan.keeps(card, is_target);
+        groups[usize::from(!is_target) * 2 + usize::from(!keep)] += 1;
+    }
+    let [kept_targets, tossed_targets, kept_others, tossed_others] = groups;
+    let targets = kept_targets + tossed_targets;
+
+    let mut total = 0.0;
+    for i in 0..=hand.min(kept_targets) {
+        for j in 0..=(hand - i).min(tossed_targets) {
+            for k in 0..=(hand - i - j).min(kept_others) {
+                let l = hand - i - j - k;
+                if l > tossed_others {
+                    continue;
+                }
+                let dealt = choose(kept_targets, i)
+                    * choose(tossed_targets, j)
+                    * choose(kept_others, k)
+                    * choose(tossed_others, l)
+                    / choose(size, hand);
+                if dealt == 0.0 {
+                    continue;
+                }
+                // Replacements come from the cards not dealt, as many as there are; then the
+                // thrown-back cards return.
+                let tossed = j + l;
+                let replacements = tossed.min(size - hand);
+                let library = size - (hand - tossed) - replacements;
+                let draws = (scenario.turn as usize).min(library);
+                let undealt_targets = targets - i - j;
+                for r in 0..=replacements.min(undealt_targets) {
+                    let replaced = hypergeometric(size - hand, undealt_targets, replacements, r);
+                    let in_hand = i + r;
+                    let in_deck = targets - in_hand;
+                    let drawn: f64 = (needed.saturating_sub(in_hand)..=draws.min(in_deck))
+                        .map(|hits| hypergeometric(library, in_deck, draws, hits))
+                        .sum();
+                    total += dealt * replaced * drawn;
+                }
+            }
+        }
+    }
+    total.min(1.0)
+}
+
+/// Estimates the same probability as [`draw_probability`] by playing out `trials` seeded deals.
+pub fn simulate_draws(
+    deck: &[Card],
+    target: impl Fn(&Card) -> bool,
+    scenario: &Scenario,
+    trials: u32,
+    seed: u64,
+) -> f64 {
+    let mut rng = Rng::new(seed);
+    let cards: Vec<(bool, bool)> = deck
+        .iter()
+        .map(|card| {
+            let is_target = target(card);
+            (is_target, scenario.mulligan.keeps(card, is_target))
+        })
+        .collect();
+    let needed = scenario.copies.saturating_sub(scenario.coin_hits(&target));
+    let hand_size = scenario.play.opening_hand().min(cards.len());
+
+    let mut hits = 0;
+    for _ in 0..trials {
+        let mut library = cards.clone();
+        rng.shuffle(&mut library);
+        let mut hand = library.split_off(library.len() - hand_size);
+        let (kept, tossed): (Vec<_>, Vec<_>) = hand.drain(..).partition(|&(_, keep)| keep);
+        hand = kept;
+        let replacements = tossed.len().min(library.len());
+        hand.extend(library.split_off(library.len() - replacements));
+        library.extend(tossed);
+        rng.shuffle(&mut library);
+        let draws = (scenario.turn as usize).min(library.len());
+        hand.extend(library.split_off(library.len() - draws));
+
+        if hand.iter().filter(|&&(is_target, _)| is_target).count() >= needed {
+            hits += 1;
+        }
+    }
+    hits as f64 / trials.max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardSet, Class, MinionType, Rarity};
+    use std::borrow::Cow;
+
+    fn deck() -> Vec<Card<'static>> {
+        (0..30)
+            .map(|i| Card::Minion {
+                id: i,
+                name: Cow::Owned(format!("Minion {i}")),
+                mana_cost: (i % 6) as u8 + 1,
+                attack: 1,
+                health: 1,
+                minion_types: if i < 4 {
+                    vec![MinionType::Pirate]
+                } else {
+                    Vec::new()
+                },
+                classes: vec![Class::Rogue],
+                rarity: Rarity::Common,
+                set: CardSet::Core,
+                text: Cow::Borrowed(""),
+            })
+            .collect()
+    }
+
+    fn one_drop_pirate(card: &Card) -> bool {
+        card.mana_cost() == 1 && card.minion_types().contains(&MinionType::Pirate)
+    }
+
+    #[test]
+    fn matches_the_closed_form_without_a_mulligan() {
+        // Only card 0 is a 1-mana Pirate.
+        let deck = deck();
+        let targets = deck.iter().filter(|c| one_drop_pirate(c)).count();
+        let scenario = Scenario {
+            mulligan: Mulligan::KeepAll,
+            ..Scenario::new(Play::First, 1)
+        };
+        let expected = 1.0 - choose(30 - targets, 4) / choose(30, 4);
+        let exact = draw_probability(&deck, one_drop_pirate, &scenario);
+        assert!((exact - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exact_odds_agree_with_simulation() {
+        let deck = deck();
+        let pirate = |card: &Card| card.minion_types().contains(&MinionType::Pirate);
+        for scenario in [
+            Scenario::new(Play::First, 3),
+            Scenario {
+                mulligan: Mulligan::KeepUpTo(2),
+                copies: 2,
+                ..Scenario::new(Play::Second, 5)
+            },
+        ] {
+            let exact = draw_probability(&deck, pirate, &scenario);
+            let simulated = simulate_draws(&deck, pirate, &scenario, 50_000, 11);
+            assert!((exact - simulated).abs() < 0.01, "{exact} vs {simulated}");
+        }
+
+        let coin = Scenario::new(Play::Second, 0);
+        assert_eq!(draw_probability(&deck, |c| c.mana_cost() == 0, &coin), 1.0);
+    }
+
+    #[test]
+    fn decks_smaller_than_hand_and_mulligan() {
+        let deck = &deck()[..5];
+        let none = |_: &Card| false;
+        let scenario = Scenario::new(Play::Second, 1);
+        assert_eq!(simulate_draws(deck, none, &scenario, 100, 3), 0.0);
+        assert_eq!(draw_probability(deck, none, &scenario), 0.0);
+
+        // Only one card is left to replace up to four thrown back.
+        let last = |card: &Card| card.id() == 4;
+        for scenario in [
+            Scenario::new(Play::Second, 0),
+            Scenario::new(Play::First, 1),
+        ] {
+            let exact = draw_probability(deck, last, &scenario);
+            let simulated = simulate_draws(deck, last, &scenario, 20_000, 5);
+            assert!((exact - simulated).abs() < 0.01, "{exact} vs {simulated}");
+        }
+    }
+}
@@ -0,0 +1,493 @@
+//! Balance patches: new mana costs, stats, text or sets for cards, by id.
+//!
+//! A patch is applied to a card pool or to saved decks. Its changelog compares each changed card
+//! before and after, calling a change a nerf if it raises the mana cost or lowers a stat, and a
+//! buff if it does the opposite. Text and set changes are neither.
+//!
+//! Patches load from JSON such as
+//! `{"name": "31.2", "changes": [{"id": 315, "mana_cost": 5, "text": "Deal $5 damage."}]}`.
+//! A change may set `mana_cost`, `attack`, `health`, `durability`, `armor`, `text` and `set`.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::card_db::{LoadError, LoadErrorKind, MAX_MANA_COST};
+use crate::deck::Deck;
+use crate::json::{self, Json, Value};
+use crate::validate::Violation;
+use crate::{Card, CardDb, CardInfo, CardSet, FlavorText, OwnedCard};
+
+/// New values for one card. Fields left as `None`, and stats the card doesn't have, are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CardChange {
+    pub mana_cost: Option<u8>,
+    pub attack: Option<u8>,
+    pub health: Option<u8>,
+    pub durability: Option<u8>,
+    pub armor: Option<u8>,
+    pub text: Option<String>,
+    pub set: Option<CardSet>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub name: String,
+    pub changes: Vec<(u32, CardChange)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    ManaCost,
+    Attack,
+    Health,
+    Durability,
+    Armor,
+    Text,
+    Set,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Field::ManaCost => "mana cost",
+            Field::Attack => "attack",
+            Field::Health => "health",
+            Field::Durability => "durability",
+            Field::Armor => "armor",
+            Field::Text => "text",
+            Field::Set => "set",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Buff,
+    Nerf,
+    Neutral,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEntry {
+    pub id: u32,
+    pub name: String,
+    pub field: Field,
+    pub before: String,
+    pub after: String,
+    pub direction: Direction,
+}
+
+/// Every field a patch changed, in the order of the patch's changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changelog {
+    pub entries: Vec<ChangeEntry>,
+}
+
+impl Changelog {
+    /// Ids of cards with at least one nerfed field.
+    pub fn nerfed(&self) -> Vec<u32> {
+        self.ids(Direction::Nerf)
+    }
+
+    pub fn buffed(&self) -> Vec<u32> {
+        self.ids(Direction::Buff)
+    }
+
+    fn ids(&self, direction: Direction) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .entries
+            .iter()
+            .filter(|e| e.direction == direction)
+            .map(|e| e.id)
+            .collect();
+        ids.dedup();
+        ids
+    }
+}
+
+/// One line per change, as in `Fireball: mana cost 4 -> 5 (nerf)`.
+impl fmt::Display for Changelog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            write!(
+                f,
+                "{}: {} {} -> {}",
+                entry.name, entry.field, entry.before, entry.after
+            )?;
+            match entry.direction {
+                Direction::Buff => writeln!(f, " (buff)")?,
+                Direction::Nerf => writeln!(f, " (nerf)")?,
+                Direction::Neutral => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a patch affects one saved deck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckReview {
+    /// The deck's position in the list given to [`Patch::review`].
+    pub index: usize,
+    pub nerfed: Vec<u32>,
+    pub buffed: Vec<u32>,
+    /// Rules the deck breaks after the patch, such as holding a card that rotated out.
+    pub violations: Vec<Violation>,
+}
+
+impl Patch {
+    pub fn new(name: impl Into<String>) -> Patch {
+        Patch {
+            name: name.into(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// The change for card `id`, added if the patch doesn't touch the card yet.
+    pub fn change(&mut self, id: u32) -> &mut CardChange {
+        let index = match self.changes.iter().position(|(changed, _)| *changed == id) {
+            Some(index) => index,
+            None => {
+                self.changes.push((id, CardChange::default()));
+                self.changes.len() - 1
+            }
+        };
+        &mut self.changes[index].1
+    }
+
+    fn change_for(&self, id: u32) -> Option<&CardChange> {
+        self.changes
+            .iter()
+            .find(|(changed, _)| *changed == id)
+            .map(|(_, change)| change)
+    }
+
+    /// The card as it is after the patch.
+    pub fn apply_to_card(&self, card: &OwnedCard) -> OwnedCard {
+        let mut card = card.clone();
+        let Some(change) = self.change_for(card.id()) else {
+            return card;
+        };
+        let (mana_cost, set, text) = match &mut card {
+            Card::Spell {
+                mana_cost,
+                set,
+                text,
+                ..
+            }
+            | Card::Minion {
+                mana_cost,
+                set,
+                text,
+                ..
+            }
+            | Card::Location {
+                mana_cost,
+                set,
+                text,
+                ..
+            }
+            | Card::Weapon {
+                mana_cost,
+                set,
+                text,
+                ..
+            }
+            | Card::Hero {
+                mana_cost,
+                set,
+                text,
+                ..
+            } => (mana_cost, set, text),
+        };
+        *mana_cost = change.mana_cost.unwrap_or(*mana_cost);
+        *set = change.set.unwrap_or(*set);
+        if let Some(new_text) = &change.text {
+            *text = Cow::Owned(new_text.clone());
+        }
+        let keep = |stat: &mut u8, new: Option<u8>| *stat = new.unwrap_or(*stat);
+        match &mut card {
+            Card::Minion { attack, health, .. } => {
+                keep(attack, change.attack);
+                keep(health, change.health);
+            }
+            Card::Weapon {
+                attack, durability, ..
+            } => {
+                keep(attack, change.attack);
+                keep(durability, change.durability);
+            }
+            Card::Location { durability, .. } => keep(durability, change.durability),
+            Card::Hero { armor, .. } => keep(armor, change.armor),
+            Card::Spell { .. } => {}
+        }
+        card
+    }
+
+    /// The post-patch pool, keeping the pool's translations and scripts.
+    pub fn apply(&self, db: &CardDb) -> CardDb {
+        let mut patched = CardDb::new(db.iter().map(|card| self.apply_to_card(card)).collect());
+        *patched.translations_mut() = db.translations().clone();
+        for card in db {
+            if let Some(script) = db.script(card.id()) {
+                patched.set_script(card.id(), script);
+            }
+        }
+        patched
+    }
+
+    /// What the patch changes about the cards in `db`. Changes to cards not in `db` are skipped.
+    pub fn changelog(&self, db: &CardDb) -> Changelog {
+        let mut log = Changelog::default();
+        for (id, _) in &self.changes {
+            if let Some(card) = db.by_id(*id) {
+                log.entries.extend(compare(card, &self.apply_to_card(card)));
+            }
+        }
+        log
+    }
+
+    /// Reviews every deck holding a changed card, re-validating it with the patched cards.
+    pub fn review(&self, decks: &[Deck]) -> Vec<DeckReview> {
+        let mut reviews = Vec::new();
+        for (index, deck) in decks.iter().enumerate() {
+            let mut patched = deck.clone();
+            let mut log = Changelog::default();
+            for (card, _) in &mut patched.cards {
+                if self.change_for(card.id()).is_some() {
+                    let after = self.apply_to_card(card);
+                    log.entries.extend(compare(card, &after));
+                    *card = after;
+                }
+            }
+            if log.entries.is_empty() {
+                continue;
+            }
+            reviews.push(DeckReview {
+                index,
+                nerfed: log.nerfed(),
+                buffed: log.buffed(),
+                violations: patched.validate().err().unwrap_or_default(),
+            });
+        }
+        reviews
+    }
+
+    pub fn from_json(src: &str) -> Result<Patch, LoadError> {
+        let root = json::parse(src).map_err(|e| LoadError {
+            line: e.line,
+            field: None,
+            kind: LoadErrorKind::Syntax(e.message),
+        })?;
+        let error = |json: &Json, field: &str, kind| LoadError {
+            line: json.line,
+            field: Some(field.to_string()),
+            kind,
+        };
+        let mut patch = Patch::new(root.get("name").and_then(Json::as_str).unwrap_or_default());
+        let changes = root
+            .get("changes")
+            .and_then(Json::as_array)
+            .ok_or_else(|| error(&root, "changes", LoadErrorKind::MissingField))?;
+
+        // A whole number from 0 to `max`.
+        let whole = |value: &Json, field: &str, max: f64| match value.as_f64() {
+            Some(n) if n.fract() == 0.0 && (0.0..=max).contains(&n) => Ok(n),
+            _ => Err(error(
+                value,
+                field,
+                LoadErrorKind::InvalidNumber(value.to_string()),
+            )),
+        };
+        for item in changes {
+            let number = |field: &str, max: u8| -> Result<Option<u8>, LoadError> {
+                item.get(field)
+                    .map(|value| whole(value, field, max as f64).map(|n| n as u8))
+                    .transpose()
+            };
+            let id = item
+                .get("id")
+                .ok_or_else(|| error(item, "id", LoadErrorKind::MissingField))?;
+            let id = whole(id, "id", u32::MAX as f64)? as u32;
+            let text = match item.get("text") {
+                Some(value) => Some(value.as_str().map(str::to_string).ok_or_else(|| {
+                    error(
+                        value,
+                        "text",
+                        LoadErrorKind::Syntax("expected a string".to_string()),
+                    )
+                })?),
+                None => None,
+            };
+            let set = match item.get("set") {
+                Some(value) => {
+                    let name = value.as_str().unwrap_or_default();
+                    Some(name.parse().map_err(|_| {
+                        error(value, "set", LoadErrorKind::UnknownValue(name.to_string()))
+                    })?)
+                }
+                None => None,
+            };
+            let change = CardChange {
+                mana_cost: number("mana_cost", MAX_MANA_COST)?,
+                attack: number("attack", u8::MAX)?,
+                health: number("health", u8::MAX)?,
+                durability: number("durability", u8::MAX)?,
+                armor: number("armor", u8::MAX)?,
+                text,
+                set,
+            };
+            *patch.change(id) = change;
+        }
+        Ok(patch)
+    }
+
+    /// The patch as JSON that [`Patch::from_json`] reads back.
+    pub fn to_json(&self) -> Value {
+        let changes = self.changes.iter().map(|(id, change)| {
+            let mut fields = vec![("id", Value::from(*id))];
+            for (field, value) in [
+                ("mana_cost", change.mana_cost),
+                ("attack", change.attack),
+                ("health", change.health),
+                ("durability", change.durability),
+                ("armor", change.armor),
+            ] {
+                if let Some(value) = value {
+                    fields.push((field, Value::from(value as u32)));
+                }
+            }
+            if let Some(text) = &change.text {
+                fields.push(("text", Value::from(text.as_str())));
+            }
+            if let Some(set) = change.set {
+                fields.push(("set", Value::from(set.name())));
+            }
+            Value::object(fields)
+        });
+        Value::object([
+            ("name", Value::from(self.name.as_str())),
+            ("changes", Value::array(changes)),
+        ])
+    }
+}
+
+/// The fields that differ between two versions of a card.
+fn compare(before: &Card, after: &Card) -> Vec<ChangeEntry> {
+    let entry = |field, old: String, new: String, direction| ChangeEntry {
+        id: after.id(),
+        name: after.name().to_string(),
+        field,
+        before: old,
+        after: new,
+        direction,
+    };
+    let mut entries = Vec::new();
+    let stats = [
+        (
+            Field::ManaCost,
+            Some(before.mana_cost()),
+            Some(after.mana_cost()),
+        ),
+        (Field::Attack, before.attack(), after.attack()),
+        (Field::Health, before.health(), after.health()),
+        (Field::Durability, before.durability(), after.durability()),
+        (Field::Armor, before.armor(), after.armor()),
+    ];
+    for (field, old, new) in stats {
+        let (Some(old), Some(new)) = (old, new) else {
+            continue;
+        };
+        if old == new {
+            continue;
+        }
+        // A higher cost is worse for the player; a higher stat is better.
+        let direction = match (field == Field::ManaCost, new > old) {
+            (true, true) | (false, false) => Direction::Nerf,
+            _ => Direction::Buff,
+        };
+        entries.push(entry(field, old.to_string(), new.to_string(), direction));
+    }
+    if before.text() != after.text() {
+        entries.push(entry(
+            Field::Text,
+            format!("\"{}\"", before.text()),
+            format!("\"{}\"", after.text()),
+            Direction::Neutral,
+        ));
+    }
+    if before.set() != after.set() {
+        entries.push(entry(
+            Field::Set,
+            before.set().to_string(),
+            after.set().to_string(),
+            Direction::Neutral,
+        ));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Class, Format};
+
+    const CARDS: &str = "type,id,name,mana_cost,class,rarity,set,attack,health,text\n\
+                         minion,3,Edwin van Cleef,3,Rogue,Legendary,Core,2,2,Combo: Gain +2/+2.\n\
+                         minion,4,Patches the Pirate,1,Neutral,Legendary,Core,1,1,Charge\n\
+                         minion,5,Southsea Deckhand,1,Neutral,Common,Core,2,1,\n";
+
+    #[test]
+    fn applies_logs_and_reviews() {
+        let db = CardDb::from_csv(CARDS).unwrap();
+        let patch = Patch::from_json(
+            r#"{"name": "31.2", "changes": [
+                {"id": 3, "mana_cost": 4, "health": 3},
+                {"id": 4, "set": "Legacy", "text": "Rush"},
+                {"id": 99, "attack": 1}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Patch::from_json(&patch.to_json().to_string()),
+            Ok(patch.clone())
+        );
+
+        let patched = patch.apply(&db);
+        assert_eq!(patched.by_id(3).unwrap().mana_cost(), 4);
+        assert_eq!(patched.by_id(4).unwrap().text(), "Rush");
+        assert_eq!(patched.by_id(5), db.by_id(5));
+
+        let log = patch.changelog(&db);
+        assert_eq!(log.nerfed(), [3]);
+        assert_eq!(log.buffed(), [3]);
+        assert_eq!(
+            log.to_string(),
+            "Edwin van Cleef: mana cost 3 -> 4 (nerf)\n\
+             Edwin van Cleef: health 2 -> 3 (buff)\n\
+             Patches the Pirate: text \"Charge\" -> \"Rush\"\n\
+             Patches the Pirate: set Core -> Legacy\n"
+        );
+
+        let mut pirates = Deck::new(Format::Standard, Class::Rogue);
+        pirates.add(db.by_id(4).unwrap().clone(), 1);
+        let mut tempo = Deck::new(Format::Standard, Class::Rogue);
+        tempo.add(db.by_id(5).unwrap().clone(), 2);
+        let reviews = patch.review(&[tempo, pirates]);
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].index, 1);
+        assert!(reviews[0].violations.contains(&Violation::NotInFormat {
+            id: 4,
+            set: CardSet::Legacy
+        }));
+
+        let err = Patch::from_json(r#"{"changes": [{"id": 3, "mana_cost": 40}]}"#).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("mana_cost"));
+        for id in ["-1", "3.7", "1e12"] {
+            let err = Patch::from_json(&format!(r#"{{"changes": [{{"id": {id}}}]}}"#)).unwrap_err();
+            assert!(matches!(err.kind, LoadErrorKind::InvalidNumber(_)), "{id}");
+        }
+        let err = Patch::from_json(r#"{"changes": [{"id": 3, "text": 5}]}"#).unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("text"));
+    }
+}
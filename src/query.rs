@@ -0,0 +1,560 @@
+//! A small search language over cards, e.g.
+//! `class:rogue mana<=3 type:minion tribe:pirate text:"combo"`.
+//!
+//! Numeric fields are `mana`, `id`, `attack`, `health`, `durability` and `armor`; the others
+//! are `name`, `text`, `class`, `type`, `tribe`, `school`, `rarity`, `set` and `keyword`.
+//!
+//! Terms next to each other must all match. `OR`, `AND`, `NOT` (or a leading `-`) and
+//! parentheses combine them. A term without a field matches the card's name or text. Text
+//! matching ignores case.
+
+use std::fmt;
+
+use crate::keywords::Keyword;
+use crate::{
+    Card, CardInfo, CardKind, CardSet, Class, FlavorText, MinionType, Rarity, SpellSchool,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryErrorKind {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnterminatedQuote,
+    UnknownField(String),
+    InvalidValue(String),
+    InvalidOperator(String),
+}
+
+/// A query that failed to parse, with the 0-based character position of the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub position: usize,
+    pub kind: QueryErrorKind,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at position {}: ", self.position)?;
+        match &self.kind {
+            QueryErrorKind::UnexpectedEnd => write!(f, "unexpected end of query"),
+            QueryErrorKind::UnexpectedToken(t) => write!(f, "unexpected \"{t}\""),
+            QueryErrorKind::UnterminatedQuote => write!(f, "unterminated quote"),
+            QueryErrorKind::UnknownField(name) => write!(f, "unknown field \"{name}\""),
+            QueryErrorKind::InvalidValue(v) => write!(f, "invalid value \"{v}\""),
+            QueryErrorKind::InvalidOperator(op) => {
+                write!(f, "operator \"{op}\" cannot be used with this field")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+type Predicate = Box<dyn Fn(&Card<'_>) -> bool + Send + Sync>;
+
+/// A compiled query.
+pub struct Query {
+    predicate: Predicate,
+}
+
+impl fmt::Debug for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Query").finish_non_exhaustive()
+    }
+}
+
+impl Query {
+    pub fn parse(src: &str) -> Result<Query, QueryError> {
+        let tokens = lex(src)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: src.chars().count(),
+        };
+        let predicate = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(token.unexpected());
+        }
+        Ok(Query { predicate })
+    }
+
+    pub fn matches(&self, card: &Card) -> bool {
+        (self.predicate)(card)
+    }
+}
+
+/// Returns the cards in `deck` that match `query`.
+pub fn search<'a, 'b>(deck: &'a [Card<'b>], query: &str) -> Result<Vec<&'a Card<'b>>, QueryError> {
+    let query = Query::parse(query)?;
+    Ok(deck.iter().filter(|card| query.matches(card)).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Has,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn compare<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Has | Op::Eq => left == right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Has => ":",
+            Op::Eq => "=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term {
+        field: Option<String>,
+        op: Op,
+        op_pos: usize,
+        value: String,
+        value_pos: usize,
+    },
+}
+
+#[derive(Debug)]
+struct Token {
+    pos: usize,
+    tok: Tok,
+}
+
+impl Token {
+    fn unexpected(&self) -> QueryError {
+        let text = match &self.tok {
+            Tok::LParen => "(".to_string(),
+            Tok::RParen => ")".to_string(),
+            Tok::And => "AND".to_string(),
+            Tok::Or => "OR".to_string(),
+            Tok::Not => "NOT".to_string(),
+            Tok::Term { value, .. } => value.clone(),
+        };
+        QueryError {
+            position: self.pos,
+            kind: QueryErrorKind::UnexpectedToken(text),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()\":<>=".contains(c)
+}
+
+fn lex(src: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let quoted = |i: &mut usize| -> Result<String, QueryError> {
+        let start = *i;
+        *i += 1;
+        let mut value = String::new();
+        while let Some(&c) = chars.get(*i) {
+            *i += 1;
+            if c == '"' {
+                return Ok(value);
+            }
+            value.push(c);
+        }
+        Err(QueryError {
+            position: start,
+            kind: QueryErrorKind::UnterminatedQuote,
+        })
+    };
+    let word = |i: &mut usize| -> String {
+        let start = *i;
+        while chars.get(*i).is_some_and(|&c| is_word_char(c)) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect()
+    };
+
+    while i < chars.len() {
+        let pos = i;
+        let tok = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Tok::LParen
+            }
+            ')' => {
+                i += 1;
+                Tok::RParen
+            }
+            '-' => {
+                i += 1;
+                Tok::Not
+            }
+            '"' => Tok::Term {
+                field: None,
+                op: Op::Has,
+                op_pos: pos,
+                value: quoted(&mut i)?,
+                value_pos: pos,
+            },
+            c if is_word_char(c) => {
+                let name = word(&mut i);
+                let op_pos = i;
+                let op = match (chars.get(i), chars.get(i + 1)) {
+                    (Some(':'), _) => Some((Op::Has, 1)),
+                    (Some('<'), Some('=')) => Some((Op::Le, 2)),
+                    (Some('>'), Some('=')) => Some((Op::Ge, 2)),
+                    (Some('<'), _) => Some((Op::Lt, 1)),
+                    (Some('>'), _) => Some((Op::Gt, 1)),
+                    (Some('='), _) => Some((Op::Eq, 1)),
+                    _ => None,
+                };
+                match op {
+                    Some((op, len)) => {
+                        i += len;
+                        let value_pos = i;
+                        let value = if chars.get(i) == Some(&'"') {
+                            quoted(&mut i)?
+                        } else {
+                            word(&mut i)
+                        };
+                        if value.is_empty() {
+                            return Err(QueryError {
+                                position: value_pos,
+                                kind: QueryErrorKind::UnexpectedEnd,
+                            });
+                        }
+                        Tok::Term {
+                            field: Some(name),
+                            op,
+                            op_pos,
+                            value,
+                            value_pos,
+                        }
+                    }
+                    None => match name.to_uppercase().as_str() {
+                        "AND" => Tok::And,
+                        "OR" => Tok::Or,
+                        "NOT" => Tok::Not,
+                        _ => Tok::Term {
+                            field: None,
+                            op: Op::Has,
+                            op_pos: pos,
+                            value: name,
+                            value_pos: pos,
+                        },
+                    },
+                }
+            }
+            c => {
+                return Err(QueryError {
+                    position: pos,
+                    kind: QueryErrorKind::UnexpectedToken(c.to_string()),
+                });
+            }
+        };
+        tokens.push(Token { pos, tok });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn or(&mut self) -> Result<Predicate, QueryError> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Tok::Or) {
+            self.pos += 1;
+            let right = self.and()?;
+            left = Box::new(move |card| left(card) || right(card));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Predicate, QueryError> {
+        let mut left = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Tok::And) => self.pos += 1,
+                Some(Tok::LParen | Tok::Not | Tok::Term { .. }) => {}
+                _ => return Ok(left),
+            }
+            let right = self.unary()?;
+            left = Box::new(move |card| left(card) && right(card));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Predicate, QueryError> {
+        if self.peek() == Some(&Tok::Not) {
+            self.pos += 1;
+            let inner = self.unary()?;
+            return Ok(Box::new(move |card| !inner(card)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Predicate, QueryError> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            return Err(QueryError {
+                position: self.end,
+                kind: QueryErrorKind::UnexpectedEnd,
+            });
+        };
+        match &token.tok {
+            Tok::LParen => {
+                self.pos += 1;
+                let inner = self.or()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token {
+                        tok: Tok::RParen, ..
+                    }) => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    Some(other) => Err(other.unexpected()),
+                    None => Err(QueryError {
+                        position: self.end,
+                        kind: QueryErrorKind::UnexpectedEnd,
+                    }),
+                }
+            }
+            Tok::Term {
+                field,
+                op,
+                op_pos,
+                value,
+                value_pos,
+            } => {
+                let predicate = term(field.as_deref(), token.pos, *op, *op_pos, value, *value_pos)?;
+                self.pos += 1;
+                Ok(predicate)
+            }
+            _ => Err(token.unexpected()),
+        }
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(needle)
+}
+
+fn term(
+    field: Option<&str>,
+    field_pos: usize,
+    op: Op,
+    op_pos: usize,
+    value: &str,
+    value_pos: usize,
+) -> Result<Predicate, QueryError> {
+    let invalid_value = || QueryError {
+        position: value_pos,
+        kind: QueryErrorKind::InvalidValue(value.to_string()),
+    };
+    let equality_only = || {
+        if matches!(op, Op::Has | Op::Eq) {
+            Ok(())
+        } else {
+            Err(QueryError {
+                position: op_pos,
+                kind: QueryErrorKind::InvalidOperator(op.symbol().to_string()),
+            })
+        }
+    };
+    let needle = value.to_lowercase();
+
+    let Some(field) = field else {
+        return Ok(Box::new(move |card| {
+            contains_ignore_case(card.name(), &needle) || contains_ignore_case(card.text(), &needle)
+        }));
+    };
+
+    Ok(match field.to_lowercase().as_str() {
+        "name" => {
+            equality_only()?;
+            Box::new(move |card| contains_ignore_case(card.name(), &needle))
+        }
+        "text" => {
+            equality_only()?;
+            Box::new(move |card| contains_ignore_case(card.text(), &needle))
+        }
+        "mana" | "cost" => {
+            let cost: u8 = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| op.compare(card.mana_cost(), cost))
+        }
+        "attack" | "health" | "durability" | "armor" => {
+            let stat: u8 = value.parse().map_err(|_| invalid_value())?;
+            let get: fn(&Card<'_>) -> Option<u8> = match field.to_lowercase().as_str() {
+                "attack" => |card| card.attack(),
+                "health" => |card| card.health(),
+                "durability" => |card| card.durability(),
+                _ => |card| card.armor(),
+            };
+            Box::new(move |card| get(card).is_some_and(|v| op.compare(v, stat)))
+        }
+        "id" => {
+            let id: u32 = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| op.compare(card.id(), id))
+        }
+        "class" => {
+            equality_only()?;
+            let class: Class = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| card.classes().contains(&class))
+        }
+        "type" => {
+            equality_only()?;
+            let kind: CardKind = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| card.kind() == kind)
+        }
+        "tribe" => {
+            equality_only()?;
+            let tribe: MinionType = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| card.minion_types().contains(&tribe))
+        }
+        "school" => {
+            equality_only()?;
+            let school: SpellSchool = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| card.spell_school() == Some(school))
+        }
+        "rarity" => {
+            let rarity: Rarity = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| op.compare(card.rarity(), rarity))
+        }
+        "set" => {
+            equality_only()?;
+            let set: CardSet = value.parse().map_err(|_| invalid_value())?;
+            Box::new(move |card| card.set() == set)
+        }
+        "keyword" | "kw" => {
+            equality_only()?;
+            let keyword = Keyword::from_name(value).ok_or_else(invalid_value)?;
+            Box::new(move |card| card.parsed_text().has_keyword(keyword))
+        }
+        _ => {
+            return Err(QueryError {
+                position: field_pos,
+                kind: QueryErrorKind::UnknownField(field.to_string()),
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    fn pool() -> Vec<Card<'static>> {
+        let minion = |id, name, mana_cost, minion_type, class, text| Card::Minion {
+            id,
+            name: Cow::Borrowed(name),
+            mana_cost,
+            attack: mana_cost,
+            health: mana_cost + 1,
+            minion_types: vec![minion_type],
+            classes: vec![class],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(text),
+        };
+        vec![
+            minion(
+                1,
+                "Edwin van Cleef",
+                3,
+                MinionType::Pirate,
+                Class::Rogue,
+                "Combo: Gain +2/+2.",
+            ),
+            minion(
+                2,
+                "Patches",
+                1,
+                MinionType::Pirate,
+                Class::Neutral,
+                "Charge",
+            ),
+            minion(
+                3,
+                "Murloc Raider",
+                1,
+                MinionType::Murloc,
+                Class::Neutral,
+                "",
+            ),
+            minion(
+                4,
+                "Big Pirate",
+                6,
+                MinionType::Pirate,
+                Class::Rogue,
+                "Combo: Draw a card.",
+            ),
+        ]
+    }
+
+    fn ids(result: Vec<&Card>) -> Vec<u32> {
+        result.iter().map(|card| card.id()).collect()
+    }
+
+    #[test]
+    fn combines_terms() {
+        let pool = pool();
+        let query = r#"class:rogue mana<=3 type:minion tribe:pirate text:"COMBO""#;
+        assert_eq!(ids(search(&pool, query).unwrap()), vec![1]);
+        assert_eq!(
+            ids(search(&pool, "(tribe:murloc OR class:rogue) -mana>5").unwrap()),
+            vec![1, 3]
+        );
+        assert_eq!(
+            ids(search(&pool, "NOT tribe:pirate OR patches").unwrap()),
+            vec![2, 3]
+        );
+        assert_eq!(ids(search(&pool, "health>=4 attack<6").unwrap()), vec![1]);
+    }
+
+    #[test]
+    fn errors_point_at_the_token() {
+        let pool = pool();
+        let err = search(&pool, "mana<=3 class:rouge").unwrap_err();
+        assert_eq!(err.position, 14);
+        assert_eq!(err.kind, QueryErrorKind::InvalidValue("rouge".to_string()));
+
+        let err = search(&pool, "mana<=3 colour:red").unwrap_err();
+        assert_eq!(err.position, 8);
+
+        let err = search(&pool, "(class:rogue").unwrap_err();
+        assert_eq!(err.kind, QueryErrorKind::UnexpectedEnd);
+        assert_eq!(err.position, 12);
+    }
+}
@@ -0,0 +1,555 @@
+//! Recording games and playing them back.
+//!
+//! A replay is a line-oriented text file. It names the seed and both decks as card ids in the
+//! order they were given to [`Game::new`], then the hash of the starting state, then one line per
+//! action with the turn it was taken on and the state hash after it:
+//!
+//! ```text
+//! get_rusty replay 1
+//! seed 42
+//! deck 3 3 5 5 1052
+//! deck 4 4 6 6 1052
+//! start 9c4b1e0f2a7d3388
+//! 1 play 0 enemy:minion:1 5e1f0c2d3b4a6978
+//! 1 attack minion:0 hero 0a1b2c3d4e5f6071
+//! 1 location 0 - 3f2e1d0c9b8a7766
+//! 1 end 77f0e1d2c3b4a596
+//! ```
+//!
+//! Targets are `-` for none or `side:character`, with `friendly` or `enemy` sides and `hero` or
+//! `minion:<index>` characters. Blank lines and lines starting with `#` are ignored.
+
+use std::fmt;
+
+use crate::game::{Action, ActionError, Character, Game, Outcome, Side, Target};
+use crate::{Card, CardDb, OwnedCard};
+
+const HEADER: &str = "get_rusty replay 1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step {
+    pub turn: u32,
+    pub action: Action,
+    /// [`Game::state_hash`] after the action.
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub decks: [Vec<u32>; 2],
+    pub start: u64,
+    pub steps: Vec<Step>,
+}
+
+/// How a re-executed game stopped matching its replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// The game refused the recorded action.
+    Rejected(ActionError),
+    /// The action was recorded on a different turn than the one the game is on.
+    Turn {
+        expected: u32,
+        found: u32,
+    },
+    StateHash {
+        expected: u64,
+        found: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    UnknownCard(u32),
+    /// The game diverged at action `action` (0-based) of the replay, taken on `turn`. The starting
+    /// state diverging is reported with `action` `None`.
+    Diverged {
+        turn: u32,
+        action: Option<usize>,
+        kind: Divergence,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ReplayError::UnknownCard(id) => write!(f, "card {id} is not in the card pool"),
+            ReplayError::Diverged { turn, action, kind } => {
+                match action {
+                    Some(action) => write!(f, "turn {turn}, action {action}: ")?,
+                    None => write!(f, "starting state: ")?,
+                }
+                match kind {
+                    Divergence::Rejected(e) => write!(f, "action rejected: {e}"),
+                    Divergence::Turn { expected, found } => {
+                        write!(
+                            f,
+                            "recorded on turn {expected} but the game is on turn {found}"
+                        )
+                    }
+                    Divergence::StateHash { expected, found } => {
+                        write!(f, "state hash {found:016x}, expected {expected:016x}")
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Plays a game while recording it.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    game: Game,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn new(first_deck: &[Card], second_deck: &[Card], seed: u64) -> Recorder {
+        let game = Game::new(first_deck, second_deck, seed);
+        let ids = |deck: &[Card]| deck.iter().map(Card::id).collect();
+        let replay = Replay {
+            seed,
+            decks: [ids(first_deck), ids(second_deck)],
+            start: game.state_hash(),
+            steps: Vec::new(),
+        };
+        Recorder { game, replay }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Applies `action`, recording it only if the game accepts it.
+    pub fn apply(&mut self, action: Action) -> Result<(), ActionError> {
+        let turn = self.game.turn();
+        self.game.apply(action)?;
+        self.replay.steps.push(Step {
+            turn,
+            action,
+            hash: self.game.state_hash(),
+        });
+        Ok(())
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+/// Re-executes a replay one action at a time, checking every state hash.
+#[derive(Debug, Clone)]
+pub struct Viewer<'r> {
+    replay: &'r Replay,
+    game: Game,
+    next: usize,
+}
+
+impl<'r> Viewer<'r> {
+    /// Starts the recorded game, looking the decks' cards up in `db`.
+    pub fn new(replay: &'r Replay, db: &CardDb) -> Result<Viewer<'r>, ReplayError> {
+        let deck = |ids: &[u32]| -> Result<Vec<OwnedCard>, ReplayError> {
+            ids.iter()
+                .map(|&id| db.by_id(id).cloned().ok_or(ReplayError::UnknownCard(id)))
+                .collect()
+        };
+        let game = Game::new(
+            &deck(&replay.decks[0])?,
+            &deck(&replay.decks[1])?,
+            replay.seed,
+        );
+        if game.state_hash() != replay.start {
+            return Err(ReplayError::Diverged {
+                turn: game.turn(),
+                action: None,
+                kind: Divergence::StateHash {
+                    expected: replay.start,
+                    found: game.state_hash(),
+                },
+            });
+        }
+        Ok(Viewer {
+            replay,
+            game,
+            next: 0,
+        })
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The index of the next action to replay.
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    /// Replays the next action, returning it, or `None` once every action has been replayed.
+    pub fn step(&mut self) -> Option<Result<&'r Step, ReplayError>> {
+        let step = self.replay.steps.get(self.next)?;
+        let index = self.next;
+        self.next += 1;
+        let diverged = |kind| ReplayError::Diverged {
+            turn: step.turn,
+            action: Some(index),
+            kind,
+        };
+        if self.game.turn() != step.turn {
+            return Some(Err(diverged(Divergence::Turn {
+                expected: step.turn,
+                found: self.game.turn(),
+            })));
+        }
+        if let Err(e) = self.game.apply(step.action) {
+            return Some(Err(diverged(Divergence::Rejected(e))));
+        }
+        let found = self.game.state_hash();
+        if found != step.hash {
+            return Some(Err(diverged(Divergence::StateHash {
+                expected: step.hash,
+                found,
+            })));
+        }
+        Some(Ok(step))
+    }
+
+    /// Replays the remaining actions and returns the final game.
+    pub fn finish(mut self) -> Result<Game, ReplayError> {
+        while let Some(step) = self.step() {
+            step?;
+        }
+        Ok(self.game)
+    }
+
+    /// The board as text: each hero, their mana, hand and deck sizes and their minions.
+    pub fn render(&self) -> String {
+        let game = &self.game;
+        let mut out = format!(
+            "Turn {}, player {} to act\n",
+            game.turn(),
+            game.current_player() + 1
+        );
+        for p in 0..2 {
+            let player = game.player(p);
+            let hero = &player.hero;
+            out.push_str(&format!(
+                "P{} | Health {}{} | Mana {}/{} | Hand {} | Deck {}\n",
+                p + 1,
+                hero.health,
+                if hero.armor > 0 {
+                    format!(" +{} armor", hero.armor)
+                } else {
+                    String::new()
+                },
+                player.mana,
+                player.max_mana,
+                player.hand.len(),
+                player.deck.len(),
+            ));
+            if !player.board.is_empty() {
+                let minions: Vec<String> = player
+                    .board
+                    .iter()
+                    .map(|m| format!("{} {}/{}", m.name, m.attack, m.health))
+                    .collect();
+                out.push_str(&format!("   | {}\n", minions.join(", ")));
+            }
+        }
+        match game.outcome() {
+            Some(Outcome::Won(p)) => out.push_str(&format!("Player {} won\n", p + 1)),
+            Some(Outcome::Draw) => out.push_str("Draw\n"),
+            None => {}
+        }
+        out
+    }
+}
+
+impl Replay {
+    /// Re-executes the whole replay against `db`, returning the final game.
+    pub fn verify(&self, db: &CardDb) -> Result<Game, ReplayError> {
+        Viewer::new(self, db)?.finish()
+    }
+
+    pub fn parse(src: &str) -> Result<Replay, ReplayError> {
+        let mut lines = src
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        let mut next = |expected: &str| {
+            lines.next().ok_or_else(|| ReplayError::Syntax {
+                line: src.lines().count(),
+                message: format!("expected {expected}"),
+            })
+        };
+
+        let (line, header) = next("the replay header")?;
+        if header != HEADER {
+            return Err(syntax(line, format!("expected \"{HEADER}\"")));
+        }
+        let (line, seed) = next("the seed")?;
+        let seed = field(line, seed, "seed")?;
+        let seed = seed
+            .parse()
+            .map_err(|_| syntax(line, format!("invalid seed \"{seed}\"")))?;
+        let mut deck = || -> Result<Vec<u32>, ReplayError> {
+            let (line, deck) = next("a deck")?;
+            field(line, deck, "deck")?
+                .split_whitespace()
+                .map(|id| {
+                    id.parse()
+                        .map_err(|_| syntax(line, format!("invalid card id \"{id}\"")))
+                })
+                .collect()
+        };
+        let decks = [deck()?, deck()?];
+        let (line, start) = next("the starting state hash")?;
+        let start = hash(line, field(line, start, "start")?)?;
+
+        let mut steps = Vec::new();
+        for (line, text) in lines {
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let (turn, action, state) = match words.as_slice() {
+                [turn, action @ .., state] if !action.is_empty() => (turn, action, state),
+                _ => return Err(syntax(line, "expected a turn, an action and a hash")),
+            };
+            steps.push(Step {
+                turn: turn
+                    .parse()
+                    .map_err(|_| syntax(line, format!("invalid turn \"{turn}\"")))?,
+                action: parse_action(action).ok_or_else(|| {
+                    syntax(line, format!("invalid action \"{}\"", action.join(" ")))
+                })?,
+                hash: hash(line, state)?,
+            });
+        }
+        Ok(Replay {
+            seed,
+            decks,
+            start,
+            steps,
+        })
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "seed {}", self.seed)?;
+        for deck in &self.decks {
+            let ids: Vec<String> = deck.iter().map(u32::to_string).collect();
+            writeln!(f, "deck {}", ids.join(" "))?;
+        }
+        writeln!(f, "start {:016x}", self.start)?;
+        for step in &self.steps {
+            writeln!(
+                f,
+                "{} {} {:016x}",
+                step.turn,
+                ActionText(step.action),
+                step.hash
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> ReplayError {
+    ReplayError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+/// The rest of a `name value` line.
+fn field<'s>(line: usize, text: &'s str, name: &str) -> Result<&'s str, ReplayError> {
+    match text.split_once(' ') {
+        Some((key, value)) if key == name => Ok(value.trim()),
+        _ if text == name => Ok(""),
+        _ => Err(syntax(line, format!("expected \"{name}\""))),
+    }
+}
+
+fn hash(line: usize, text: &str) -> Result<u64, ReplayError> {
+    u64::from_str_radix(text, 16).map_err(|_| syntax(line, format!("invalid hash \"{text}\"")))
+}
+
+struct ActionText(Action);
+
+impl fmt::Display for ActionText {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn character(c: Character) -> String {
+            match c {
+                Character::Hero => "hero".to_string(),
+                Character::Minion(i) => format!("minion:{i}"),
+            }
+        }
+        fn target(t: Option<Target>) -> String {
+            match t {
+                None => "-".to_string(),
+                Some(Target { side, character: c }) => {
+                    let side = match side {
+                        Side::Friendly => "friendly",
+                        Side::Enemy => "enemy",
+                    };
+                    format!("{side}:{}", character(c))
+                }
+            }
+        }
+        match self.0 {
+            Action::PlayCard {
+                hand_index,
+                target: t,
+            } => {
+                write!(f, "play {hand_index} {}", target(t))
+            }
+            Action::Attack { attacker, defender } => {
+                write!(f, "attack {} {}", character(attacker), character(defender))
+            }
+            Action::UseLocation { index, target: t } => write!(f, "location {index} {}", target(t)),
+            Action::EndTurn => write!(f, "end"),
+        }
+    }
+}
+
+fn parse_action(words: &[&str]) -> Option<Action> {
+    fn character(text: &str) -> Option<Character> {
+        match text.split_once(':') {
+            None if text == "hero" => Some(Character::Hero),
+            Some(("minion", i)) => Some(Character::Minion(i.parse().ok()?)),
+            _ => None,
+        }
+    }
+    fn target(text: &str) -> Option<Option<Target>> {
+        if text == "-" {
+            return Some(None);
+        }
+        let (side, c) = text.split_once(':')?;
+        let side = match side {
+            "friendly" => Side::Friendly,
+            "enemy" => Side::Enemy,
+            _ => return None,
+        };
+        Some(Some(Target {
+            side,
+            character: character(c)?,
+        }))
+    }
+    Some(match words {
+        ["play", index, t] => Action::PlayCard {
+            hand_index: index.parse().ok()?,
+            target: target(t)?,
+        },
+        ["attack", attacker, defender] => Action::Attack {
+            attacker: character(attacker)?,
+            defender: character(defender)?,
+        },
+        ["location", index, t] => Action::UseLocation {
+            index: index.parse().ok()?,
+            target: target(t)?,
+        },
+        ["end"] => Action::EndTurn,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardSet, Class, Rarity};
+    use std::borrow::Cow;
+
+    fn db() -> CardDb {
+        CardDb::new(
+            (1..=6)
+                .map(|id| Card::Minion {
+                    id,
+                    name: Cow::Owned(format!("Minion {id}")),
+                    mana_cost: (id % 3) as u8,
+                    attack: 2,
+                    health: 2,
+                    minion_types: Vec::new(),
+                    classes: vec![Class::Neutral],
+                    rarity: Rarity::Common,
+                    set: CardSet::Core,
+                    text: Cow::Borrowed(if id == 1 { "Charge" } else { "" }),
+                })
+                .collect(),
+        )
+    }
+
+    fn record(db: &CardDb) -> Replay {
+        let deck: Vec<OwnedCard> = db.iter().chain(db.iter()).cloned().collect();
+        let mut recorder = Recorder::new(&deck, &deck, 9);
+        while recorder.game().turn() < 8 {
+            let _ = recorder.apply(Action::PlayCard {
+                hand_index: 0,
+                target: None,
+            });
+            let _ = recorder.apply(Action::Attack {
+                attacker: Character::Minion(0),
+                defender: Character::Hero,
+            });
+            recorder.apply(Action::EndTurn).unwrap();
+        }
+        recorder.finish()
+    }
+
+    #[test]
+    fn round_trips_and_verifies() {
+        let db = db();
+        let replay = record(&db);
+        assert!(replay.steps.len() > 8);
+        let text = replay.to_string();
+        assert_eq!(Replay::parse(&text), Ok(replay.clone()));
+
+        let mut viewer = Viewer::new(&replay, &db).unwrap();
+        assert_eq!(viewer.step(), Some(Ok(&replay.steps[0])));
+        assert!(viewer.render().starts_with("Turn "));
+        assert_eq!(
+            replay.verify(&db).unwrap().state_hash(),
+            replay.steps.last().unwrap().hash
+        );
+    }
+
+    #[test]
+    fn reports_where_the_game_diverged() {
+        let db = db();
+        let mut replay = record(&db);
+        replay.steps[3].hash ^= 1;
+        match replay.verify(&db) {
+            Err(ReplayError::Diverged {
+                action: Some(3),
+                kind: Divergence::StateHash { .. },
+                ..
+            }) => {}
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let mut replay = record(&db);
+        replay.steps.insert(1, replay.steps[0]);
+        let Err(ReplayError::Diverged { turn, action, .. }) = replay.verify(&db) else {
+            panic!("a repeated action should diverge");
+        };
+        assert_eq!((turn, action), (replay.steps[1].turn, Some(1)));
+
+        assert_eq!(
+            Replay::parse("get_rusty replay 1\nseed x\n"),
+            Err(ReplayError::Syntax {
+                line: 2,
+                message: "invalid seed \"x\"".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,1003 @@
+//! A small language for card effects, compiled to bytecode and run against a board of its own.
+//!
+//! A script is a list of statements separated by `;`. Each statement is an effect, optionally
+//! behind a trigger and a condition:
+//!
+//! ```text
+//! battlecry: deal 2 to chosen
+//! deathrattle: summon 2/1 "Pirate"
+//! end_of_turn: if friendly_minions >= 3 and enemy_health < 10 then buff all_friendly_minions +1/+1
+//! draw cards_in_hand; heal 3 to friendly_hero; discover spell
+//! ```
+//!
+//! Statements without a trigger run when the card is played. The effects are `deal <n> to
+//! <target>`, `heal <n> to <target>`, `summon <attack>/<health> ["name"]`, `draw <n>`, `buff
+//! <target> +<attack>/+<health>` and `discover [card type]`. A number may also be one of the
+//! quantities `friendly_minions`, `enemy_minions`, `cards_in_hand`, `health` and `enemy_health`,
+//! compared in conditions with `<`, `<=`, `>`, `>=`, `==` and `!=`. Targets are `self`, `chosen`,
+//! `friendly_hero`, `enemy_hero`, `random_enemy`, `random_enemy_minion`, `all_enemy_minions`,
+//! `all_friendly_minions`, `all_minions`, `all_enemies` and `all_characters`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::game::{Character, MAX_BOARD, MAX_HAND, STARTING_HEALTH, Side, Target};
+use crate::rng::Rng;
+use crate::{Card, CardDb, CardInfo, CardKind, OwnedCard};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedQuote,
+    InvalidNumber(String),
+    /// `found` is `None` at the end of the script.
+    Expected {
+        expected: &'static str,
+        found: Option<String>,
+    },
+}
+
+/// A script that failed to compile, with the card it belongs to and the 1-based line and column
+/// of the offending character or token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub card: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ScriptErrorKind,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, line {}, column {}: ",
+            self.card, self.line, self.column
+        )?;
+        match &self.kind {
+            ScriptErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected '{c}'"),
+            ScriptErrorKind::UnterminatedQuote => write!(f, "unterminated quote"),
+            ScriptErrorKind::InvalidNumber(n) => write!(f, "\"{n}\" is too large"),
+            ScriptErrorKind::Expected {
+                expected,
+                found: Some(found),
+            } => write!(f, "expected {expected}, found \"{found}\""),
+            ScriptErrorKind::Expected {
+                expected,
+                found: None,
+            } => write!(f, "expected {expected}, found the end of the script"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Play,
+    Battlecry,
+    Deathrattle,
+    EndOfTurn,
+}
+
+/// A number read from the board, from the point of view of the script's owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantity {
+    FriendlyMinions,
+    EnemyMinions,
+    CardsInHand,
+    Health,
+    EnemyHealth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn holds(self, a: i32, b: i32) -> bool {
+        match self {
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b,
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Selector {
+    /// The minion running the script, while it is on the board.
+    Itself,
+    /// The target the card was played on.
+    Chosen,
+    FriendlyHero,
+    EnemyHero,
+    RandomEnemy,
+    RandomEnemyMinion,
+    AllEnemyMinions,
+    AllFriendlyMinions,
+    AllMinions,
+    AllEnemies,
+    AllCharacters,
+}
+
+/// One instruction. Instructions taking numbers pop them from the stack, the last pushed being
+/// the last operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Push(i32),
+    Load(Quantity),
+    /// Pops two numbers and pushes 1 if the comparison holds, 0 otherwise.
+    Compare(Comparison),
+    /// Pops a number and jumps to the instruction at the given index if it is 0.
+    JumpIfZero(usize),
+    Deal(Selector),
+    Heal(Selector),
+    /// Pops attack and health.
+    Buff(Selector),
+    /// Pops attack and health and summons a token named by the script's name at this index.
+    Summon(usize),
+    Draw,
+    Discover(Option<CardKind>),
+}
+
+/// A compiled script: the code run for each trigger it uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    hooks: Vec<(Trigger, Vec<Op>)>,
+    names: Vec<String>,
+}
+
+impl Script {
+    /// Compiles `src`, naming `card` in errors.
+    pub fn compile(card: &str, src: &str) -> Result<Script, ScriptError> {
+        let error = |((line, column), kind)| ScriptError {
+            card: card.to_string(),
+            line,
+            column,
+            kind,
+        };
+        let tokens = lex(src).map_err(error)?;
+        let last_line = src.rsplit('\n').next().unwrap_or_default();
+        let mut compiler = Compiler {
+            tokens,
+            pos: 0,
+            end: (src.matches('\n').count() + 1, last_line.chars().count() + 1),
+            script: Script::default(),
+        };
+        compiler.script().map_err(error)?;
+        Ok(compiler.script)
+    }
+
+    /// The code run on `trigger`, empty if the script doesn't use it.
+    pub fn hook(&self, trigger: Trigger) -> &[Op] {
+        self.hooks
+            .iter()
+            .find(|(t, _)| *t == trigger)
+            .map_or(&[], |(_, code)| code)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Word(String),
+    Number(i32),
+    Str(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tok::Word(word) => write!(f, "{word}"),
+            Tok::Number(n) => write!(f, "{n}"),
+            Tok::Str(s) => write!(f, "\"{s}\""),
+            Tok::Symbol(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// A 1-based line and column.
+type Position = (usize, usize);
+
+type Failure = (Position, ScriptErrorKind);
+
+/// Splits `src` into tokens, each with its position.
+fn lex(src: &str) -> Result<Vec<(Position, Tok)>, Failure> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut line, mut line_start) = (1, 0);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let position = (line, i - line_start + 1);
+        if c.is_whitespace() {
+            i += 1;
+            if c == '\n' {
+                (line, line_start) = (line + 1, i);
+            }
+            continue;
+        }
+        let tok = if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Tok::Word(chars[start..i].iter().collect::<String>().to_lowercase())
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let n = digits
+                .parse()
+                .map_err(|_| (position, ScriptErrorKind::InvalidNumber(digits)))?;
+            Tok::Number(n)
+        } else if c == '"' {
+            let Some(len) = chars[i + 1..].iter().position(|&c| c == '"') else {
+                return Err((position, ScriptErrorKind::UnterminatedQuote));
+            };
+            i += len + 2;
+            // A name may run over several lines.
+            for (offset, _) in chars[start..i]
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c == '\n')
+            {
+                (line, line_start) = (line + 1, start + offset + 1);
+            }
+            Tok::Str(chars[start + 1..i - 1].iter().collect())
+        } else {
+            let next = chars.get(i + 1).copied();
+            let (symbol, len) = match (c, next) {
+                ('<', Some('=')) => ("<=", 2),
+                ('>', Some('=')) => (">=", 2),
+                ('=', Some('=')) => ("==", 2),
+                ('!', Some('=')) => ("!=", 2),
+                ('<', _) => ("<", 1),
+                ('>', _) => (">", 1),
+                (':', _) => (":", 1),
+                (';', _) => (";", 1),
+                ('/', _) => ("/", 1),
+                ('+', _) => ("+", 1),
+                _ => return Err((position, ScriptErrorKind::UnexpectedCharacter(c))),
+            };
+            i += len;
+            Tok::Symbol(symbol)
+        };
+        tokens.push((position, tok));
+    }
+    Ok(tokens)
+}
+
+struct Compiler {
+    tokens: Vec<(Position, Tok)>,
+    pos: usize,
+    /// The position just past the end of the script.
+    end: Position,
+    script: Script,
+}
+
+impl Compiler {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(_, tok)| tok)
+    }
+
+    fn expected(&self, expected: &'static str) -> Failure {
+        let (position, found) = match self.tokens.get(self.pos) {
+            Some((position, tok)) => (*position, Some(tok.to_string())),
+            None => (self.end, None),
+        };
+        (position, ScriptErrorKind::Expected { expected, found })
+    }
+
+    /// Takes the next token if it is the word or symbol `text`.
+    fn eat(&mut self, text: &str) -> bool {
+        let matches = match self.peek() {
+            Some(Tok::Word(word)) => word == text,
+            Some(Tok::Symbol(symbol)) => *symbol == text,
+            _ => false,
+        };
+        self.pos += usize::from(matches);
+        matches
+    }
+
+    fn expect(&mut self, text: &'static str) -> Result<(), Failure> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.expected(text))
+        }
+    }
+
+    /// Reads one of the words in `options`, returning its value.
+    fn choice<T: Copy>(
+        &mut self,
+        expected: &'static str,
+        options: &[(&str, T)],
+    ) -> Result<T, Failure> {
+        if let Some(Tok::Word(word)) = self.peek()
+            && let Some(&(_, value)) = options.iter().find(|(name, _)| name == word)
+        {
+            self.pos += 1;
+            return Ok(value);
+        }
+        Err(self.expected(expected))
+    }
+
+    fn script(&mut self) -> Result<(), Failure> {
+        while self.peek().is_some() {
+            if !self.eat(";") {
+                self.statement()?;
+                if self.peek().is_some() {
+                    self.expect(";")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), Failure> {
+        let trigger = match self.tokens.get(self.pos + 1) {
+            Some((_, Tok::Symbol(":"))) => {
+                let trigger = self.choice(
+                    "a trigger",
+                    &[
+                        ("battlecry", Trigger::Battlecry),
+                        ("deathrattle", Trigger::Deathrattle),
+                        ("end_of_turn", Trigger::EndOfTurn),
+                    ],
+                )?;
+                self.pos += 1;
+                trigger
+            }
+            _ => Trigger::Play,
+        };
+        let mut code = Vec::new();
+        let mut jumps = Vec::new();
+        if self.eat("if") {
+            loop {
+                self.value(&mut code)?;
+                let comparison = match self.peek() {
+                    Some(Tok::Symbol("<")) => Comparison::Less,
+                    Some(Tok::Symbol("<=")) => Comparison::LessOrEqual,
+                    Some(Tok::Symbol(">")) => Comparison::Greater,
+                    Some(Tok::Symbol(">=")) => Comparison::GreaterOrEqual,
+                    Some(Tok::Symbol("==")) => Comparison::Equal,
+                    Some(Tok::Symbol("!=")) => Comparison::NotEqual,
+                    _ => return Err(self.expected("a comparison")),
+                };
+                self.pos += 1;
+                self.value(&mut code)?;
+                code.push(Op::Compare(comparison));
+                jumps.push(code.len());
+                code.push(Op::JumpIfZero(0));
+                if !self.eat("and") {
+                    break;
+                }
+            }
+            self.expect("then")?;
+        }
+        self.effect(&mut code)?;
+
+        let hook = match self.script.hooks.iter().position(|(t, _)| *t == trigger) {
+            Some(i) => &mut self.script.hooks[i].1,
+            None => {
+                self.script.hooks.push((trigger, Vec::new()));
+                &mut self.script.hooks.last_mut().unwrap().1
+            }
+        };
+        let offset = hook.len();
+        for i in jumps {
+            code[i] = Op::JumpIfZero(offset + code.len());
+        }
+        hook.extend(code);
+        Ok(())
+    }
+
+    fn effect(&mut self, code: &mut Vec<Op>) -> Result<(), Failure> {
+        let effect = self.choice(
+            "an effect",
+            &[
+                ("deal", "deal"),
+                ("heal", "heal"),
+                ("summon", "summon"),
+                ("draw", "draw"),
+                ("buff", "buff"),
+                ("discover", "discover"),
+            ],
+        )?;
+        match effect {
+            "deal" | "heal" => {
+                self.value(code)?;
+                self.expect("to")?;
+                let target = self.selector()?;
+                code.push(if effect == "deal" {
+                    Op::Deal(target)
+                } else {
+                    Op::Heal(target)
+                });
+            }
+            "summon" => {
+                self.stats(code)?;
+                let name = match self.peek() {
+                    Some(Tok::Str(name)) => {
+                        let name = name.clone();
+                        self.pos += 1;
+                        name
+                    }
+                    _ => "Token".to_string(),
+                };
+                code.push(Op::Summon(self.script.names.len()));
+                self.script.names.push(name);
+            }
+            "draw" => {
+                self.value(code)?;
+                code.push(Op::Draw);
+            }
+            "buff" => {
+                let target = self.selector()?;
+                self.stats(code)?;
+                code.push(Op::Buff(target));
+            }
+            _ => {
+                let kind = match self.peek() {
+                    Some(Tok::Word(word)) => match word.parse() {
+                        Ok(kind) => {
+                            self.pos += 1;
+                            Some(kind)
+                        }
+                        Err(_) => return Err(self.expected("a card type")),
+                    },
+                    _ => None,
+                };
+                code.push(Op::Discover(kind));
+            }
+        }
+        Ok(())
+    }
+
+    /// `attack/health`, each optionally with a leading `+`.
+    fn stats(&mut self, code: &mut Vec<Op>) -> Result<(), Failure> {
+        self.eat("+");
+        self.value(code)?;
+        self.expect("/")?;
+        self.eat("+");
+        self.value(code)
+    }
+
+    fn value(&mut self, code: &mut Vec<Op>) -> Result<(), Failure> {
+        if let Some(&Tok::Number(n)) = self.peek() {
+            self.pos += 1;
+            code.push(Op::Push(n));
+            return Ok(());
+        }
+        let quantity = self.choice(
+            "a number",
+            &[
+                ("friendly_minions", Quantity::FriendlyMinions),
+                ("enemy_minions", Quantity::EnemyMinions),
+                ("cards_in_hand", Quantity::CardsInHand),
+                ("health", Quantity::Health),
+                ("enemy_health", Quantity::EnemyHealth),
+            ],
+        )?;
+        code.push(Op::Load(quantity));
+        Ok(())
+    }
+
+    fn selector(&mut self) -> Result<Selector, Failure> {
+        self.choice(
+            "a target",
+            &[
+                ("self", Selector::Itself),
+                ("chosen", Selector::Chosen),
+                ("friendly_hero", Selector::FriendlyHero),
+                ("enemy_hero", Selector::EnemyHero),
+                ("random_enemy", Selector::RandomEnemy),
+                ("random_enemy_minion", Selector::RandomEnemyMinion),
+                ("all_enemy_minions", Selector::AllEnemyMinions),
+                ("all_friendly_minions", Selector::AllFriendlyMinions),
+                ("all_minions", Selector::AllMinions),
+                ("all_enemies", Selector::AllEnemies),
+                ("all_characters", Selector::AllCharacters),
+            ],
+        )
+    }
+}
+
+/// A minion on the interpreter's board. `serial` tells apart minions that share a card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub serial: u32,
+    /// 0 for tokens.
+    pub card: u32,
+    pub name: String,
+    pub attack: i32,
+    pub health: i32,
+    pub max_health: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardSide {
+    pub health: i32,
+    pub max_health: i32,
+    pub minions: Vec<Unit>,
+    pub hand: Vec<OwnedCard>,
+    /// The top of the deck is the last card.
+    pub deck: Vec<OwnedCard>,
+}
+
+/// The state scripts run against: two heroes, their minions, hands and decks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Board {
+    pub sides: [BoardSide; 2],
+    next_serial: u32,
+}
+
+impl Board {
+    pub fn new(decks: [Vec<OwnedCard>; 2]) -> Board {
+        Board {
+            sides: decks.map(|deck| BoardSide {
+                health: STARTING_HEALTH,
+                max_health: STARTING_HEALTH,
+                minions: Vec::new(),
+                hand: Vec::new(),
+                deck,
+            }),
+            next_serial: 1,
+        }
+    }
+
+    /// Puts a minion on `player`'s board, returning its serial, or `None` if the board is full.
+    pub fn summon(
+        &mut self,
+        player: usize,
+        card: u32,
+        name: &str,
+        attack: i32,
+        health: i32,
+    ) -> Option<u32> {
+        let side = &mut self.sides[player];
+        if side.minions.len() >= MAX_BOARD {
+            return None;
+        }
+        let serial = self.next_serial;
+        self.next_serial += 1;
+        side.minions.push(Unit {
+            serial,
+            card,
+            name: name.to_string(),
+            attack,
+            health: health.max(1),
+            max_health: health.max(1),
+        });
+        Some(serial)
+    }
+
+    fn unit(&mut self, serial: u32) -> Option<&mut Unit> {
+        self.sides
+            .iter_mut()
+            .flat_map(|side| side.minions.iter_mut())
+            .find(|unit| unit.serial == serial)
+    }
+}
+
+/// A hero, by player, or a minion, by serial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subject {
+    Hero(usize),
+    Unit(u32),
+}
+
+/// Who a hook runs for.
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    player: usize,
+    source: Option<u32>,
+    chosen: Option<Subject>,
+}
+
+/// Compiled scripts for a card pool, the board they run against and the source of randomness.
+#[derive(Debug, Clone)]
+pub struct Interpreter {
+    scripts: HashMap<u32, Script>,
+    pool: Vec<OwnedCard>,
+    board: Board,
+    rng: Rng,
+}
+
+impl Interpreter {
+    /// Compiles the scripts of every card in `db`, which also supplies the cards to discover.
+    pub fn new(db: &CardDb, board: Board, seed: u64) -> Result<Interpreter, ScriptError> {
+        let mut scripts = HashMap::new();
+        for card in db {
+            if let Some(src) = db.script(card.id()) {
+                scripts.insert(card.id(), Script::compile(card.name(), src)?);
+            }
+        }
+        Ok(Interpreter {
+            scripts,
+            pool: db.cards().to_vec(),
+            board,
+            rng: Rng::new(seed),
+        })
+    }
+
+    pub fn script(&self, id: u32) -> Option<&Script> {
+        self.scripts.get(&id)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    /// Plays `card` for `player` on `target`, which is seen from `player`'s side. A minion goes
+    /// on the board first, if there is room; then the card's play and battlecry hooks run.
+    pub fn play(&mut self, player: usize, card: &Card, target: Option<Target>) {
+        let source = match card {
+            Card::Minion { attack, health, .. } => self.board.summon(
+                player,
+                card.id(),
+                card.name(),
+                *attack as i32,
+                *health as i32,
+            ),
+            _ => None,
+        };
+        let chosen = target.and_then(|target| {
+            let owner = match target.side {
+                Side::Friendly => player,
+                Side::Enemy => 1 - player,
+            };
+            match target.character {
+                Character::Hero => Some(Subject::Hero(owner)),
+                Character::Minion(i) => self.board.sides[owner]
+                    .minions
+                    .get(i)
+                    .map(|unit| Subject::Unit(unit.serial)),
+            }
+        });
+        let context = Context {
+            player,
+            source,
+            chosen,
+        };
+        self.run(card.id(), Trigger::Play, context);
+        self.run(card.id(), Trigger::Battlecry, context);
+    }
+
+    /// Runs the end-of-turn hooks of `player`'s minions, left to right.
+    pub fn end_turn(&mut self, player: usize) {
+        let minions: Vec<(u32, u32)> = self.board.sides[player]
+            .minions
+            .iter()
+            .map(|unit| (unit.serial, unit.card))
+            .collect();
+        for (serial, card) in minions {
+            if self.board.unit(serial).is_some() {
+                let context = Context {
+                    player,
+                    source: Some(serial),
+                    chosen: None,
+                };
+                self.run(card, Trigger::EndOfTurn, context);
+            }
+        }
+    }
+
+    fn run(&mut self, card: u32, trigger: Trigger, context: Context) {
+        let Some(script) = self.scripts.get(&card) else {
+            return;
+        };
+        let code = script.hook(trigger).to_vec();
+        let names = script.names.clone();
+        let me = context.player;
+        let mut stack: Vec<i32> = Vec::new();
+        let mut pc = 0;
+        while let Some(&op) = code.get(pc) {
+            pc += 1;
+            let mut pop = || stack.pop().unwrap_or(0);
+            match op {
+                Op::Push(n) => stack.push(n),
+                Op::Load(quantity) => {
+                    let sides = &self.board.sides;
+                    stack.push(match quantity {
+                        Quantity::FriendlyMinions => sides[me].minions.len() as i32,
+                        Quantity::EnemyMinions => sides[1 - me].minions.len() as i32,
+                        Quantity::CardsInHand => sides[me].hand.len() as i32,
+                        Quantity::Health => sides[me].health,
+                        Quantity::EnemyHealth => sides[1 - me].health,
+                    });
+                }
+                Op::Compare(comparison) => {
+                    let (b, a) = (pop(), pop());
+                    stack.push(i32::from(comparison.holds(a, b)));
+                }
+                Op::JumpIfZero(to) => {
+                    if pop() == 0 {
+                        pc = to;
+                    }
+                }
+                Op::Deal(selector) => {
+                    let amount = pop().max(0);
+                    for subject in self.select(selector, context) {
+                        self.change_health(subject, -amount);
+                    }
+                }
+                Op::Heal(selector) => {
+                    let amount = pop().max(0);
+                    for subject in self.select(selector, context) {
+                        self.change_health(subject, amount);
+                    }
+                }
+                Op::Buff(selector) => {
+                    let (health, attack) = (pop(), pop());
+                    for subject in self.select(selector, context) {
+                        if let Subject::Unit(serial) = subject
+                            && let Some(unit) = self.board.unit(serial)
+                        {
+                            unit.attack = unit.attack.saturating_add(attack).max(0);
+                            unit.max_health = unit.max_health.saturating_add(health);
+                            unit.health = unit.health.saturating_add(health);
+                        }
+                    }
+                }
+                Op::Summon(name) => {
+                    let (health, attack) = (pop(), pop());
+                    self.board
+                        .summon(me, 0, &names[name], attack.max(0), health);
+                }
+                Op::Draw => {
+                    for _ in 0..pop().max(0) {
+                        let side = &mut self.board.sides[me];
+                        match side.deck.pop() {
+                            Some(card) if side.hand.len() < MAX_HAND => side.hand.push(card),
+                            Some(_) => {}
+                            None => break,
+                        }
+                    }
+                }
+                Op::Discover(kind) => {
+                    let mut options: Vec<&OwnedCard> = self
+                        .pool
+                        .iter()
+                        .filter(|card| kind.is_none_or(|kind| card.kind() == kind))
+                        .collect();
+                    self.rng.shuffle(&mut options);
+                    options.truncate(3);
+                    let side = &mut self.board.sides[me];
+                    if let Some(card) = self.rng.choose(&options)
+                        && side.hand.len() < MAX_HAND
+                    {
+                        side.hand.push((*card).clone());
+                    }
+                }
+            }
+        }
+        // Deaths wait for the hook to finish, so a minion dying doesn't shift the ones after it.
+        self.resolve_deaths(me);
+    }
+
+    /// Characters picked by `selector`, heroes before minions.
+    fn select(&mut self, selector: Selector, context: Context) -> Vec<Subject> {
+        let me = context.player;
+        let units = |player: usize| -> Vec<Subject> {
+            self.board.sides[player]
+                .minions
+                .iter()
+                .filter(|unit| unit.health > 0)
+                .map(|unit| Subject::Unit(unit.serial))
+                .collect()
+        };
+        match selector {
+            Selector::Itself => context.source.map(Subject::Unit).into_iter().collect(),
+            Selector::Chosen => context.chosen.into_iter().collect(),
+            Selector::FriendlyHero => vec![Subject::Hero(me)],
+            Selector::EnemyHero => vec![Subject::Hero(1 - me)],
+            Selector::RandomEnemy => {
+                let mut enemies = units(1 - me);
+                enemies.insert(0, Subject::Hero(1 - me));
+                self.rng.choose(&enemies).copied().into_iter().collect()
+            }
+            Selector::RandomEnemyMinion => {
+                let enemies = units(1 - me);
+                self.rng.choose(&enemies).copied().into_iter().collect()
+            }
+            Selector::AllEnemyMinions => units(1 - me),
+            Selector::AllFriendlyMinions => units(me),
+            Selector::AllMinions => [units(me), units(1 - me)].concat(),
+            Selector::AllEnemies => [vec![Subject::Hero(1 - me)], units(1 - me)].concat(),
+            Selector::AllCharacters => [
+                vec![Subject::Hero(me), Subject::Hero(1 - me)],
+                units(me),
+                units(1 - me),
+            ]
+            .concat(),
+        }
+    }
+
+    /// Deals damage for a negative `amount` and heals, up to the maximum, for a positive one.
+    fn change_health(&mut self, subject: Subject, amount: i32) {
+        let (health, max) = match subject {
+            Subject::Hero(player) => {
+                let side = &mut self.board.sides[player];
+                (&mut side.health, side.max_health)
+            }
+            Subject::Unit(serial) => match self.board.unit(serial) {
+                Some(unit) => (&mut unit.health, unit.max_health),
+                None => return,
+            },
+        };
+        *health = if amount > 0 {
+            health.saturating_add(amount).min(max.max(*health))
+        } else {
+            health.saturating_add(amount)
+        };
+    }
+
+    /// Removes dead minions, the acting player's first, and runs their deathrattles, until no
+    /// more die.
+    fn resolve_deaths(&mut self, acting: usize) {
+        loop {
+            let mut dead = Vec::new();
+            for player in [acting, 1 - acting] {
+                let minions = &mut self.board.sides[player].minions;
+                dead.extend(
+                    minions
+                        .iter()
+                        .filter(|unit| unit.health <= 0)
+                        .map(|unit| (player, unit.card)),
+                );
+                minions.retain(|unit| unit.health > 0);
+            }
+            if dead.is_empty() {
+                return;
+            }
+            for (player, card) in dead {
+                let context = Context {
+                    player,
+                    source: None,
+                    chosen: None,
+                };
+                self.run(card, Trigger::Deathrattle, context);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardSet, Class, Rarity};
+    use std::borrow::Cow;
+
+    fn minion(id: u32, name: &'static str, attack: u8, health: u8) -> OwnedCard {
+        Card::Minion {
+            id,
+            name: Cow::Borrowed(name),
+            mana_cost: 2,
+            attack,
+            health,
+            minion_types: Vec::new(),
+            classes: vec![Class::Neutral],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(""),
+        }
+    }
+
+    #[test]
+    fn runs_triggers_against_the_board() {
+        let mut db = CardDb::new(vec![
+            minion(1, "Fire Elemental", 6, 5),
+            minion(2, "Harvest Golem", 2, 3),
+            minion(3, "Cheerleader", 1, 1),
+            minion(4, "Scholar", 1, 1),
+        ]);
+        db.set_script(1, "battlecry: deal 3 to chosen");
+        db.set_script(2, "deathrattle: summon 2/1 \"Damaged Golem\"");
+        db.set_script(
+            3,
+            "end_of_turn: if friendly_minions >= 2 and enemy_health < 30 then buff all_friendly_minions +1/+1",
+        );
+        db.set_script(4, "draw 2; discover minion");
+        let board = Board::new([vec![minion(2, "Harvest Golem", 2, 3); 3], Vec::new()]);
+        let mut vm = Interpreter::new(&db, board, 1).unwrap();
+
+        vm.play(1, db.by_id(2).unwrap(), None);
+        vm.play(
+            0,
+            db.by_id(1).unwrap(),
+            Some(Target {
+                side: Side::Enemy,
+                character: Character::Minion(0),
+            }),
+        );
+        let enemy = &vm.board().sides[1].minions;
+        assert_eq!(enemy.len(), 1);
+        assert_eq!(
+            (enemy[0].name.as_str(), enemy[0].attack),
+            ("Damaged Golem", 2)
+        );
+
+        vm.play(0, db.by_id(3).unwrap(), None);
+        vm.end_turn(0);
+        assert_eq!(vm.board().sides[0].minions[1].attack, 1);
+        vm.board_mut().sides[1].health -= 1;
+        vm.end_turn(0);
+        assert_eq!(vm.board().sides[0].minions[0].attack, 7);
+        assert_eq!(vm.board().sides[0].minions[1].health, 2);
+
+        vm.play(0, db.by_id(4).unwrap(), None);
+        assert_eq!(vm.board().sides[0].hand.len(), 3);
+        assert_eq!(vm.board().sides[0].deck.len(), 1);
+    }
+
+    #[test]
+    fn large_numbers_saturate() {
+        let mut db = CardDb::new(vec![minion(1, "Overkill", 1, 1), minion(2, "Titan", 1, 1)]);
+        db.set_script(
+            1,
+            "deal 2147483647 to enemy_hero; deal 2147483647 to enemy_hero",
+        );
+        db.set_script(2, "buff all_friendly_minions +2147483647/+2147483647");
+        let mut vm = Interpreter::new(&db, Board::new([Vec::new(), Vec::new()]), 1).unwrap();
+
+        vm.play(0, db.by_id(1).unwrap(), None);
+        assert_eq!(vm.board().sides[1].health, i32::MIN);
+        vm.play(0, db.by_id(2).unwrap(), None);
+        vm.play(0, db.by_id(2).unwrap(), None);
+        let unit = &vm.board().sides[0].minions[0];
+        assert_eq!((unit.attack, unit.health), (i32::MAX, i32::MAX));
+    }
+
+    #[test]
+    fn errors_name_the_card_line_and_column() {
+        let err = Script::compile("Fireball", "deal 6 to enemy").unwrap_err();
+        assert_eq!(err.column, 11);
+        assert_eq!(
+            err.to_string(),
+            "Fireball, line 1, column 11: expected a target, found \"enemy\""
+        );
+        let err = Script::compile("Pyro", "battlecry: if health > 3 deal 1 to self").unwrap_err();
+        assert_eq!(err.column, 26);
+        let err = Script::compile("Coin", "draw 1;\n summon 1/1 \"Coin").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (2, 13, ScriptErrorKind::UnterminatedQuote)
+        );
+        let err = Script::compile("X", "heal 2 to").unwrap_err();
+        assert_eq!(err.column, 10);
+        let err = Script::compile("Z", "summon 1/1 \"Two\nLines\";\ndraw").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 5));
+
+        let script = Script::compile("Y", "if cards_in_hand < 3 then draw 1; draw 1").unwrap();
+        assert_eq!(
+            script.hook(Trigger::Play),
+            [
+                Op::Load(Quantity::CardsInHand),
+                Op::Push(3),
+                Op::Compare(Comparison::Less),
+                Op::JumpIfZero(6),
+                Op::Push(1),
+                Op::Draw,
+                Op::Push(1),
+                Op::Draw,
+            ]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Deck statistics: mana curve, average cost and card counts by kind, class, school, tribe and
+//! keyword.
+
+use std::fmt;
+
+use crate::deck::Deck;
+use crate::keywords::Keyword;
+use crate::{Card, CardInfo, CardKind, Class, MinionType, SpellSchool};
+
+/// Costs of 7 or more share the last bucket of the mana curve.
+pub const CURVE_BUCKETS: usize = 8;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeckStats {
+    pub total: usize,
+    pub mana_curve: [usize; CURVE_BUCKETS],
+    pub average_cost: f64,
+    pub by_kind: Vec<(CardKind, usize)>,
+    /// Multi-class cards count once for each of their classes.
+    pub by_class: Vec<(Class, usize)>,
+    pub by_spell_school: Vec<(SpellSchool, usize)>,
+    /// Minions with several tribes count once for each tribe.
+    pub by_minion_type: Vec<(MinionType, usize)>,
+    pub by_keyword: Vec<(Keyword, usize)>,
+}
+
+/// Counts how many cards match each value in `all`, leaving out values no card has.
+fn count_by<T: Copy + PartialEq>(
+    deck: &[Card],
+    all: impl IntoIterator<Item = T>,
+    has: impl Fn(&Card, T) -> bool,
+) -> Vec<(T, usize)> {
+    all.into_iter()
+        .map(|value| (value, deck.iter().filter(|card| has(card, value)).count()))
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}
+
+impl DeckStats {
+    pub fn new(deck: &[Card]) -> DeckStats {
+        let mut mana_curve = [0; CURVE_BUCKETS];
+        for card in deck {
+            mana_curve[(card.mana_cost() as usize).min(CURVE_BUCKETS - 1)] += 1;
+        }
+        let total_cost: usize = deck.iter().map(|card| card.mana_cost() as usize).sum();
+        let average_cost = if deck.is_empty() {
+            0.0
+        } else {
+            total_cost as f64 / deck.len() as f64
+        };
+        let keywords: Vec<Vec<Keyword>> = deck
+            .iter()
+            .map(|card| card.parsed_text().keywords())
+            .collect();
+
+        DeckStats {
+            total: deck.len(),
+            mana_curve,
+            average_cost,
+            by_kind: count_by(deck, CardKind::ALL.iter().copied(), |card, kind| {
+                card.kind() == kind
+            }),
+            by_class: count_by(deck, Class::ALL.iter().copied(), |card, class| {
+                card.classes().contains(&class)
+            }),
+            by_spell_school: count_by(deck, SpellSchool::ALL.iter().copied(), |card, school| {
+                card.spell_school() == Some(school)
+            }),
+            by_minion_type: count_by(deck, MinionType::ALL.iter().copied(), |card, tribe| {
+                card.minion_types().contains(&tribe)
+            }),
+            by_keyword: Keyword::all()
+                .map(|k| (k, keywords.iter().filter(|ks| ks.contains(&k)).count()))
+                .filter(|&(_, count)| count > 0)
+                .collect(),
+        }
+    }
+
+    /// Renders the report as box-drawn tables.
+    pub fn table(&self) -> String {
+        let mut out = format!(
+            "Cards: {} | Average cost: {:.2}\n",
+            self.total, self.average_cost
+        );
+
+        let curve: Vec<(String, String)> = self
+            .mana_curve
+            .iter()
+            .enumerate()
+            .map(|(cost, &count)| {
+                let label = if cost == CURVE_BUCKETS - 1 {
+                    format!("{cost}+")
+                } else {
+                    cost.to_string()
+                };
+                (label, format!("{count:>2} {}", "█".repeat(count)))
+            })
+            .collect();
+        render_table(&mut out, "Mana", &curve);
+
+        fn rows<T: fmt::Display>(counts: &[(T, usize)]) -> Vec<(String, String)> {
+            counts
+                .iter()
+                .map(|(value, count)| (value.to_string(), count.to_string()))
+                .collect()
+        }
+        render_table(&mut out, "Type", &rows(&self.by_kind));
+        render_table(&mut out, "Class", &rows(&self.by_class));
+        render_table(&mut out, "Spell school", &rows(&self.by_spell_school));
+        render_table(&mut out, "Minion type", &rows(&self.by_minion_type));
+        render_table(&mut out, "Keyword", &rows(&self.by_keyword));
+        out
+    }
+}
+
+impl fmt::Display for DeckStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.table())
+    }
+}
+
+impl Deck {
+    pub fn stats(&self) -> DeckStats {
+        DeckStats::new(&self.expand())
+    }
+}
+
+/// Appends a two-column table with a `title` / "Cards" header, skipping empty tables.
+pub(crate) fn render_table(out: &mut String, title: &str, rows: &[(String, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+    let width = |s: &str| s.chars().count();
+    let left = rows
+        .iter()
+        .map(|(label, _)| width(label))
+        .chain([width(title)])
+        .max()
+        .unwrap_or(0);
+    let right = rows
+        .iter()
+        .map(|(_, value)| width(value))
+        .chain([width("Cards")])
+        .max()
+        .unwrap_or(0);
+    let line = |l: &str, m: &str, r: &str| {
+        format!(
+            "{l}{}{m}{}{r}\n",
+            "─".repeat(left + 2),
+            "─".repeat(right + 2)
+        )
+    };
+
+    out.push_str(&line("┌", "┬", "┐"));
+    out.push_str(&format!("│ {title:<left$} │ {:<right$} │\n", "Cards"));
+    out.push_str(&line("├", "┼", "┤"));
+    for (label, value) in rows {
+        out.push_str(&format!("│ {label:<left$} │ {value:<right$} │\n"));
+    }
+    out.push_str(&line("└", "┴", "┘"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CardSet, Rarity};
+    use std::borrow::Cow;
+
+    #[test]
+    fn counts_curve_and_categories() {
+        let spell = |mana_cost, text| Card::Spell {
+            id: mana_cost as u32,
+            name: Cow::Borrowed("Spell"),
+            mana_cost,
+            spell_school: SpellSchool::Fire,
+            classes: vec![Class::Mage],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed(text),
+        };
+        let minion = Card::Minion {
+            id: 100,
+            name: Cow::Borrowed("Minion"),
+            mana_cost: 2,
+            attack: 2,
+            health: 2,
+            minion_types: vec![MinionType::Pirate, MinionType::Murloc],
+            classes: vec![Class::Neutral],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Borrowed("Taunt"),
+        };
+        let deck = vec![
+            spell(1, "Deal $3 damage."),
+            spell(9, "Discover a spell."),
+            minion,
+        ];
+
+        let stats = DeckStats::new(&deck);
+        assert_eq!(stats.mana_curve, [0, 1, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(stats.average_cost, 4.0);
+        assert_eq!(
+            stats.by_kind,
+            vec![(CardKind::Spell, 2), (CardKind::Minion, 1)]
+        );
+        assert_eq!(
+            stats.by_minion_type,
+            vec![(MinionType::Murloc, 1), (MinionType::Pirate, 1)]
+        );
+        assert_eq!(
+            stats.by_keyword,
+            vec![(Keyword::Taunt, 1), (Keyword::Discover, 1)]
+        );
+        assert!(stats.table().contains("│ 7+   │  1 █  │"));
+    }
+}
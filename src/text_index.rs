@@ -0,0 +1,324 @@
+//! Full-text search over card names and text through an inverted index.
+//!
+//! Words are folded like names in [`lookup`](crate::lookup), after markup is stripped, so
+//! `<b>Battlecry:</b>` indexes as `battlecry`. A query is a list of words and `"quoted
+//! phrases"`, all of which a card must contain; a phrase must appear as consecutive words in the
+//! name or in the text. Hits are ranked by how rare the matched terms are across the pool and how
+//! often they occur in the card, with matches in the name counting double.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::lookup::fold;
+use crate::markup::{Renderer, Style};
+use crate::{CardInfo, FlavorText, OwnedCard};
+
+/// Positions of one word in one card. Name words come first, then text words after a gap so a
+/// phrase can't span the two.
+///
+/// Each word's postings are kept in document order: an inserted card always gets the next
+/// document number, and removing a card drops its postings without reordering the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Posting {
+    doc: usize,
+    positions: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Doc {
+    card: OwnedCard,
+    name_len: u32,
+    words: HashSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit<'i> {
+    pub card: &'i OwnedCard,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextIndex {
+    /// Removed cards leave a `None` so the document numbers of the others stay put, until
+    /// these outnumber the cards and the index is compacted.
+    docs: Vec<Option<Doc>>,
+    by_id: HashMap<u32, usize>,
+    postings: HashMap<String, Vec<Posting>>,
+    len: usize,
+}
+
+/// Splits text into folded words, dropping markup.
+fn words(text: &str) -> Vec<String> {
+    Renderer::new(Style::Plain)
+        .render(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .map(fold)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Word(String),
+    Phrase(Vec<String>),
+}
+
+/// Reads words and quoted phrases. An unclosed quote runs to the end of the query.
+fn parse_query(query: &str) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        if i % 2 == 1 {
+            match words(part).as_slice() {
+                [] => {}
+                [word] => terms.push(Term::Word(word.clone())),
+                phrase => terms.push(Term::Phrase(phrase.to_vec())),
+            }
+        } else {
+            terms.extend(words(part).into_iter().map(Term::Word));
+        }
+    }
+    terms
+}
+
+impl TextIndex {
+    pub fn new(cards: impl IntoIterator<Item = OwnedCard>) -> TextIndex {
+        let mut index = TextIndex::default();
+        for card in cards {
+            index.insert(card);
+        }
+        index
+    }
+
+    /// Adds a card, replacing any card already indexed with the same id.
+    pub fn insert(&mut self, card: OwnedCard) {
+        self.remove(card.id());
+        let doc = self.docs.len();
+        let name = words(card.name());
+        let name_len = name.len() as u32;
+        let all = name.into_iter().chain(words(card.text()));
+
+        let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+        for (i, word) in all.enumerate() {
+            let position = i as u32 + u32::from(i as u32 >= name_len);
+            positions.entry(word).or_default().push(position);
+        }
+        let words = positions.keys().cloned().collect();
+        for (word, positions) in positions {
+            self.postings
+                .entry(word)
+                .or_default()
+                .push(Posting { doc, positions });
+        }
+        self.by_id.insert(card.id(), doc);
+        self.docs.push(Some(Doc {
+            card,
+            name_len,
+            words,
+        }));
+        self.len += 1;
+    }
+
+    /// Removes the card with `id`, returning it if it was indexed.
+    pub fn remove(&mut self, id: u32) -> Option<OwnedCard> {
+        let doc = self.by_id.remove(&id)?;
+        let removed = self.docs[doc].take()?;
+        for word in &removed.words {
+            if let Some(postings) = self.postings.get_mut(word) {
+                postings.retain(|p| p.doc != doc);
+                if postings.is_empty() {
+                    self.postings.remove(word);
+                }
+            }
+        }
+        self.len -= 1;
+        if self.docs.len() - self.len > self.len {
+            self.compact();
+        }
+        Some(removed.card)
+    }
+
+    /// Drops the slots of removed cards, renumbering the rest in their order so postings stay
+    /// sorted.
+    fn compact(&mut self) {
+        let mut renumbered = vec![None; self.docs.len()];
+        let mut docs = Vec::with_capacity(self.len);
+        for (old, doc) in std::mem::take(&mut self.docs).into_iter().enumerate() {
+            if let Some(doc) = doc {
+                renumbered[old] = Some(docs.len());
+                docs.push(Some(doc));
+            }
+        }
+        let renumber = |doc: &mut usize| {
+            *doc = renumbered[*doc].expect("removed cards have no postings");
+        };
+        self.postings
+            .values_mut()
+            .flatten()
+            .for_each(|posting| renumber(&mut posting.doc));
+        self.by_id.values_mut().for_each(renumber);
+        self.docs = docs;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Cards containing every word and phrase of `query`, best match first. Ties keep id order.
+    pub fn search(&self, query: &str) -> Vec<Hit<'_>> {
+        let terms = parse_query(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut scores: Option<HashMap<usize, f64>> = None;
+        for term in &terms {
+            let matches = self.matches(term);
+            let idf = (1.0 + self.len as f64 / matches.len().max(1) as f64).ln();
+            let mut next = HashMap::new();
+            for (doc, (in_name, in_text)) in matches {
+                let previous = match &scores {
+                    Some(scores) => match scores.get(&doc) {
+                        Some(&score) => score,
+                        None => continue,
+                    },
+                    None => 0.0,
+                };
+                let weight = (2 * in_name + in_text) as f64;
+                next.insert(doc, previous + idf * weight / (weight + 1.0));
+            }
+            scores = Some(next);
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(doc, score)| {
+                let card = &self.docs[doc].as_ref()?.card;
+                Some(Hit { card, score })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.card.id().cmp(&b.card.id()))
+        });
+        hits
+    }
+
+    /// For each card matching `term`, how many times it occurs in the name and in the text.
+    fn matches(&self, term: &Term) -> HashMap<usize, (u32, u32)> {
+        let mut found = HashMap::new();
+        let (first, rest) = match term {
+            Term::Word(word) => (word, &[][..]),
+            Term::Phrase(words) => (&words[0], &words[1..]),
+        };
+        let Some(postings) = self.postings.get(first) else {
+            return found;
+        };
+        for posting in postings {
+            let Some(doc) = &self.docs[posting.doc] else {
+                continue;
+            };
+            let follows = |offset: usize, word: &String| -> Option<HashSet<u32>> {
+                let postings = self.postings.get(word)?;
+                let i = postings
+                    .binary_search_by_key(&posting.doc, |p| p.doc)
+                    .ok()?;
+                let p = &postings[i];
+                Some(
+                    p.positions
+                        .iter()
+                        .filter_map(|&pos| pos.checked_sub(offset as u32))
+                        .collect(),
+                )
+            };
+            let mut starts: HashSet<u32> = posting.positions.iter().copied().collect();
+            for (i, word) in rest.iter().enumerate() {
+                match follows(i + 1, word) {
+                    Some(next) => starts.retain(|start| next.contains(start)),
+                    None => starts.clear(),
+                }
+            }
+            if starts.is_empty() {
+                continue;
+            }
+            let in_name = starts.iter().filter(|&&p| p < doc.name_len).count() as u32;
+            found.insert(posting.doc, (in_name, starts.len() as u32 - in_name));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, CardSet, Class, Rarity, SpellSchool};
+    use std::borrow::Cow;
+
+    fn spell(id: u32, name: &str, text: &str) -> OwnedCard {
+        Card::Spell {
+            id,
+            name: Cow::Owned(name.to_string()),
+            mana_cost: 1,
+            spell_school: SpellSchool::Fire,
+            classes: vec![Class::Mage],
+            rarity: Rarity::Common,
+            set: CardSet::Core,
+            text: Cow::Owned(text.to_string()),
+        }
+    }
+
+    fn ids(hits: &[Hit]) -> Vec<u32> {
+        hits.iter().map(|hit| hit.card.id()).collect()
+    }
+
+    #[test]
+    fn searches_words_and_phrases() {
+        let mut index = TextIndex::new([
+            spell(1, "Fireball", "Deal $6 damage."),
+            spell(2, "Flamestrike", "Deal $5 damage to all enemy minions."),
+            spell(
+                3,
+                "Arcane Missiles",
+                "Deal $3 damage randomly split among all enemies.",
+            ),
+            spell(
+                4,
+                "Damage Control",
+                "<b>Discover</b> a spell that deals damage.",
+            ),
+        ]);
+        assert_eq!(index.len(), 4);
+        assert_eq!(ids(&index.search("damage")), [4, 1, 2, 3]);
+        assert_eq!(ids(&index.search("\"all enemy\"")), [2]);
+        assert_eq!(ids(&index.search("\"enemy all\"")), Vec::<u32>::new());
+        assert_eq!(ids(&index.search("deal \"5 damage\"")), [2]);
+        assert_eq!(ids(&index.search("discover")), [4]);
+        assert_eq!(ids(&index.search("\"missiles deal\"")), Vec::<u32>::new());
+
+        index.insert(spell(5, "Pyroblast", "Deal $10 damage to all minions."));
+        index.insert(spell(
+            2,
+            "Flamestrike",
+            "Deal $4 damage to all enemy minions.",
+        ));
+        assert_eq!(index.len(), 5);
+        assert_eq!(ids(&index.search("\"all minions\"")), [5]);
+        assert_eq!(ids(&index.search("4")), [2]);
+        assert!(index.remove(1).is_some());
+        assert!(index.search("fireball").is_empty());
+
+        for damage in 0..100 {
+            index.insert(spell(
+                3,
+                "Arcane Missiles",
+                &format!("Deal ${damage} damage."),
+            ));
+        }
+        assert!(index.docs.len() <= 2 * index.len());
+        assert_eq!(ids(&index.search("\"99 damage\"")), [3]);
+        assert_eq!(ids(&index.search("\"all minions\"")), [5]);
+    }
+}
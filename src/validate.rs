@@ -0,0 +1,181 @@
+//! Deck construction rules: deck size, copy limits, class restrictions and format legality.
+
+use std::fmt;
+
+use crate::deck::{Deck, Format};
+use crate::names::join;
+use crate::{CardSet, Class, Rarity};
+
+pub const DECK_SIZE: usize = 30;
+
+/// Sets in the current Standard rotation, alongside `CardSet::Core`.
+const STANDARD_SETS: [CardSet; 6] = [
+    CardSet::WhizbangsWorkshop,
+    CardSet::PerilsInParadise,
+    CardSet::TheGreatDarkBeyond,
+    CardSet::IntoTheEmeraldDream,
+    CardSet::TheLostCityOfUnGoro,
+    CardSet::AcrossTheTimeways,
+];
+
+impl CardSet {
+    /// Whether cards from this set may be played in `format`. The Classic set is only playable
+    /// in Classic, and Twist is modelled as everything Wild allows.
+    pub fn is_legal_in(self, format: Format) -> bool {
+        match format {
+            Format::Classic => self == CardSet::Classic,
+            Format::Standard => self == CardSet::Core || STANDARD_SETS.contains(&self),
+            Format::Wild | Format::Twist => self != CardSet::Classic,
+        }
+    }
+}
+
+impl Rarity {
+    /// The most copies of a card of this rarity a deck may hold.
+    pub fn copy_limit(self) -> u8 {
+        match self {
+            Rarity::Legendary => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    DeckSize { count: usize },
+    TooManyCopies { id: u32, count: u8, limit: u8 },
+    WrongClass { id: u32, classes: Vec<Class> },
+    NotInFormat { id: u32, set: CardSet },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::DeckSize { count } => {
+                write!(f, "deck has {count} cards, expected {DECK_SIZE}")
+            }
+            Violation::TooManyCopies { id, count, limit } => {
+                write!(f, "card {id}: {count} copies, at most {limit} allowed")
+            }
+            Violation::WrongClass { id, classes } => {
+                write!(
+                    f,
+                    "card {id}: {} card in another class's deck",
+                    join(classes)
+                )
+            }
+            Violation::NotInFormat { id, set } => {
+                write!(f, "card {id}: {set} is not legal in this format")
+            }
+        }
+    }
+}
+
+impl Deck {
+    /// Checks the deck against the construction rules, collecting every violation.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+
+        let count = self.len();
+        if count != DECK_SIZE {
+            violations.push(Violation::DeckSize { count });
+        }
+
+        for (card, copies) in &self.cards {
+            let id = card.id();
+
+            let limit = card.rarity().copy_limit();
+            if *copies > limit {
+                violations.push(Violation::TooManyCopies {
+                    id,
+                    count: *copies,
+                    limit,
+                });
+            }
+            let classes = card.classes();
+            if !classes.contains(&Class::Neutral) && !classes.contains(&self.class) {
+                violations.push(Violation::WrongClass {
+                    id,
+                    classes: classes.to_vec(),
+                });
+            }
+            let set = card.set();
+            if !set.is_legal_in(self.format) {
+                violations.push(Violation::NotInFormat { id, set });
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Card;
+    use std::borrow::Cow;
+
+    fn minion(id: u32, class: Class, rarity: Rarity, set: CardSet) -> Card<'static> {
+        Card::Minion {
+            id,
+            name: Cow::Borrowed("Test Minion"),
+            mana_cost: 2,
+            attack: 2,
+            health: 3,
+            minion_types: vec![crate::MinionType::Pirate],
+            classes: vec![class],
+            rarity,
+            set,
+            text: Cow::Borrowed(""),
+        }
+    }
+
+    #[test]
+    fn accepts_a_legal_deck() {
+        let mut deck = Deck::new(Format::Standard, Class::Rogue);
+        for id in 0..15 {
+            deck.add(minion(id, Class::Rogue, Rarity::Common, CardSet::Core), 2);
+        }
+        assert_eq!(deck.validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut deck = Deck::new(Format::Standard, Class::Rogue);
+        deck.add(
+            minion(1, Class::Neutral, Rarity::Legendary, CardSet::Core),
+            2,
+        );
+        deck.add(minion(2, Class::Mage, Rarity::Rare, CardSet::Core), 1);
+        deck.add(minion(3, Class::Rogue, Rarity::Common, CardSet::Legacy), 3);
+
+        assert_eq!(
+            deck.validate(),
+            Err(vec![
+                Violation::DeckSize { count: 6 },
+                Violation::TooManyCopies {
+                    id: 1,
+                    count: 2,
+                    limit: 1
+                },
+                Violation::WrongClass {
+                    id: 2,
+                    classes: vec![Class::Mage]
+                },
+                Violation::TooManyCopies {
+                    id: 3,
+                    count: 3,
+                    limit: 2
+                },
+                Violation::NotInFormat {
+                    id: 3,
+                    set: CardSet::Legacy
+                },
+            ])
+        );
+    }
+}